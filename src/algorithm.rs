@@ -0,0 +1,159 @@
+use std::fmt;
+use std::str::FromStr;
+use movement::{Movement, format_movements};
+use parser::{self, ParseError};
+use simplify;
+
+/// A sequence of movements.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct Algorithm {
+    pub movements: Vec<Movement>,
+}
+
+impl Algorithm {
+    pub fn new(movements: Vec<Movement>) -> Self {
+        Algorithm { movements }
+    }
+
+    pub fn len(&self) -> usize {
+        self.movements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.movements.is_empty()
+    }
+
+    /// The algorithm that undoes this one.
+    pub fn inverse(&self) -> Algorithm {
+        Algorithm::new(self.movements.iter().rev().map(Movement::inverse).collect())
+    }
+
+    /// Swap the left and right sides, as when performing the algorithm
+    /// with the other hand.
+    pub fn mirror_left_right(&self) -> Algorithm {
+        Algorithm::new(self.movements.iter().map(Movement::mirror_left_right).collect())
+    }
+
+    /// Swap the front and back sides.
+    pub fn mirror_front_back(&self) -> Algorithm {
+        Algorithm::new(self.movements.iter().map(Movement::mirror_front_back).collect())
+    }
+
+    /// Perform the algorithm `times` times in a row.
+    pub fn repeat(&self, times: usize) -> Algorithm {
+        let mut movements = Vec::with_capacity(self.len() * times);
+        for _ in 0..times {
+            movements.extend(self.movements.iter().cloned());
+        }
+        Algorithm::new(movements)
+    }
+
+    /// Perform `self` and then `other`.
+    pub fn then(&self, other: &Algorithm) -> Algorithm {
+        let mut movements = self.movements.clone();
+        movements.extend(other.movements.iter().cloned());
+        Algorithm::new(movements)
+    }
+
+    /// Remove redundant movements.  See `simplify::simplify`.
+    pub fn simplify(&self) -> Algorithm {
+        Algorithm::new(simplify::simplify(&self.movements))
+    }
+
+    /// The commutator `[a, b]`, that is `a b a' b'`.
+    pub fn commutator(a: &Algorithm, b: &Algorithm) -> Algorithm {
+        a.then(b).then(&a.inverse()).then(&b.inverse())
+    }
+
+    /// The conjugate `[a: b]`, that is `a b a'`.
+    pub fn conjugate(a: &Algorithm, b: &Algorithm) -> Algorithm {
+        a.then(b).then(&a.inverse())
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        parser::parse(s).map(Algorithm::new)
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}", format_movements(&self.movements))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cube::Cube;
+
+    fn alg(s: &str) -> Algorithm {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_inverse() {
+        let sune = alg("R U R' U R U2 R'");
+        assert_eq!(alg("R U2' R' U' R U' R'"), sune.inverse());
+
+        let mut cube = Cube::new();
+        cube.apply_all(&alg("r U x' M2 S E' f2 y").movements);
+        cube.apply_all(&alg("r U x' M2 S E' f2 y").inverse().movements);
+        assert_eq!(Cube::new(), cube);
+    }
+
+    #[test]
+    fn test_mirror() {
+        let sune = alg("R U R' U R U2 R'");
+        assert_eq!(alg("L' U' L U' L' U2 L"), sune.mirror_left_right());
+        assert_eq!(alg("R' U' R U' R' U2 R"), sune.mirror_front_back());
+        assert_eq!(alg("l' M x y' z'"), alg("r M x y z").mirror_left_right());
+        assert_eq!(alg("b' S z x' y'"), alg("f S z x y").mirror_front_back());
+
+        // Mirroring a mirror gives back the original.
+        let alg = alg("r U R' F2 M' E S x y' z2");
+        assert_eq!(alg, alg.mirror_left_right().mirror_left_right());
+        assert_eq!(alg, alg.mirror_front_back().mirror_front_back());
+    }
+
+    #[test]
+    fn test_mirror_solves_mirrored_state() {
+        // The T permutation mirrored is still a T permutation, so
+        // doing it twice solves the cube.
+        let t = alg("R U R' U' R' F R2 U' R' U' R U R' F'");
+        let mut cube = Cube::new();
+        cube.apply_all(&t.mirror_left_right().repeat(2).movements);
+        assert_eq!(Cube::new(), cube);
+    }
+
+    #[test]
+    fn test_repeat() {
+        let sexy = alg("R U R' U'");
+        assert_eq!(alg("(R U R' U')3"), sexy.repeat(3));
+
+        let mut cube = Cube::new();
+        cube.apply_all(&sexy.repeat(6).movements);
+        assert_eq!(Cube::new(), cube);
+    }
+
+    #[test]
+    fn test_commutator_conjugate() {
+        assert_eq!(alg("[R, U]"), Algorithm::commutator(&alg("R"), &alg("U")));
+        assert_eq!(alg("[F: R U R' U']"),
+                   Algorithm::conjugate(&alg("F"), &alg("R U R' U'")));
+    }
+
+    #[test]
+    fn test_simplify() {
+        assert_eq!(alg("R2 U"), alg("R R' R2 U").simplify());
+        assert!(alg("[R U: F]").then(&alg("[R U: F]").inverse()).simplify().is_empty());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("R U R' U'", alg("[R, U]").to_string());
+    }
+}
@@ -10,16 +10,30 @@ pub enum Color {
     Orange,
 }
 
+impl Color {
+    /// The red, green and blue parts of the color as it is drawn.
+    pub fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            Color::Red => (183, 18, 52),
+            Color::Blue => (0, 70, 173),
+            Color::White => (255, 255, 255),
+            Color::Green => (0, 155, 72),
+            Color::Yellow => (255, 213, 0),
+            Color::Orange => (255, 88, 0),
+        }
+    }
+}
+
 impl fmt::Debug for Color {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         write!(fmt, "{}",
-               match self {
-                   &Color::Red => "R",
-                   &Color::Blue => "B",
-                   &Color::White => "W",
-                   &Color::Green => "G",
-                   &Color::Orange => "O",
-                   &Color::Yellow => "Y",
+               match *self {
+                   Color::Red => "R",
+                   Color::Blue => "B",
+                   Color::White => "W",
+                   Color::Green => "G",
+                   Color::Orange => "O",
+                   Color::Yellow => "Y",
                })
     }
 }
@@ -0,0 +1,390 @@
+use std::error;
+use std::fmt;
+use std::sync::OnceLock;
+use cube::Cube;
+use cubies::{self, Cubies};
+use validation::ValidationError;
+
+/// The number of corner orientation coordinates, `3^7`.
+pub const CORNER_ORIENTATIONS: usize = 2187;
+/// The number of side orientation coordinates, `2^11`.
+pub const SIDE_ORIENTATIONS: usize = 2048;
+/// The number of corner permutation coordinates, `8!`.
+pub const CORNER_PERMUTATIONS: usize = 40320;
+/// The number of side permutation coordinates, `12!`.
+pub const SIDE_PERMUTATIONS: usize = 479001600;
+
+/// A cube's state as four numbers.
+///
+/// The orientations leave out the last piece since it is decided by
+/// the others on a solvable cube.  The permutations are the index of
+/// the permutation in lexicographic order.  All four are 0 for a
+/// solved cube.  Pieces are numbered as in `Cubies`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Coordinates {
+    /// `0..CORNER_ORIENTATIONS`
+    pub corner_orientation: u16,
+    /// `0..SIDE_ORIENTATIONS`, the edge orientation.
+    pub side_orientation: u16,
+    /// `0..CORNER_PERMUTATIONS`
+    pub corner_permutation: u16,
+    /// `0..SIDE_PERMUTATIONS`, the edge permutation.
+    pub side_permutation: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CoordinateError {
+    /// A coordinate is too large.
+    OutOfRange,
+    /// The coordinates describe a cube that cannot be solved.
+    Invalid(ValidationError),
+}
+
+impl fmt::Display for CoordinateError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CoordinateError::OutOfRange => write!(fmt, "a coordinate is out of range"),
+            CoordinateError::Invalid(ref error) => write!(fmt, "{}", error),
+        }
+    }
+}
+
+impl error::Error for CoordinateError {}
+
+/// Encode orientations, each below `base`, leaving out the last one.
+pub fn orientation_index(orientation: &[u8], base: u8) -> usize {
+    orientation[..orientation.len() - 1].iter()
+        .fold(0, |index, &o| index * base as usize + o as usize)
+}
+
+/// Decode an `orientation_index`.  The last orientation is chosen so
+/// they add up to a multiple of `base`.
+pub fn orientation_from_index(mut index: usize, base: u8, orientation: &mut [u8]) {
+    let last = orientation.len() - 1;
+    let mut sum = 0;
+    for o in orientation[..last].iter_mut().rev() {
+        *o = (index % base as usize) as u8;
+        sum += *o;
+        index /= base as usize;
+    }
+    orientation[last] = (base - sum % base) % base;
+}
+
+/// The index of the permutation in lexicographic order.
+pub fn permutation_index(permutation: &[u8]) -> usize {
+    let mut index = 0;
+    for i in 0..permutation.len() {
+        let smaller = permutation[i + 1..].iter().filter(|&&p| p < permutation[i]).count();
+        index = index * (permutation.len() - i) + smaller;
+    }
+    index
+}
+
+/// Decode a `permutation_index`.
+pub fn permutation_from_index(mut index: usize, permutation: &mut [u8]) {
+    let n = permutation.len();
+    // Read the digits of the factorial number system, last first.
+    for i in (0..n).rev() {
+        permutation[i] = (index % (n - i)) as u8;
+        index /= n - i;
+    }
+    // Each digit counts the unused values smaller than its value.
+    let mut unused: Vec<u8> = (0..n as u8).collect();
+    for p in permutation.iter_mut() {
+        *p = unused.remove(*p as usize);
+    }
+}
+
+pub fn binomial(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    (0..k).fold(1, |result, i| result * (n - i) / (i + 1))
+}
+
+/// Encode which slots are chosen.  With `k` slots chosen out of `n`
+/// the index is below `binomial(n, k)`.
+pub fn combination_index(chosen: &[bool]) -> usize {
+    let mut index = 0;
+    let mut k = 0;
+    for (slot, _) in chosen.iter().enumerate().filter(|&(_, &c)| c) {
+        k += 1;
+        index += binomial(slot, k);
+    }
+    index
+}
+
+/// Decode a `combination_index` of `k` chosen slots.
+pub fn combination_from_index(mut index: usize, k: usize, chosen: &mut [bool]) {
+    for c in chosen.iter_mut() {
+        *c = false;
+    }
+    let mut slot = chosen.len();
+    for k in (1..k + 1).rev() {
+        slot -= 1;
+        while binomial(slot, k) > index {
+            slot -= 1;
+        }
+        index -= binomial(slot, k);
+        chosen[slot] = true;
+    }
+}
+
+/// For each side slot, 1 if its sticker deciding whether a side is
+/// flipped by quarter turns of the top and bottom differs from the
+/// one in `Cubies`.  It is the front or back sticker on the front and
+/// back layers and the top or bottom sticker on the others.
+const FLIP_REFERENCE: [u8; 12] = [1, 0, 1, 0, 0, 0, 0, 0, 1, 0, 1, 0];
+
+/// For each corner slot, how far clockwise its left or right sticker
+/// is from its top or bottom one.
+const TWIST_REFERENCE: [u8; 8] = [2, 1, 2, 1, 1, 2, 1, 2];
+
+/// The sides in the slice between the left and right faces.
+pub const MIDDLE_SIDES: [u8; 4] = [0, 2, 8, 10];
+/// The sides in the slice between the top and bottom faces.
+pub const EQUATOR_SIDES: [u8; 4] = [4, 5, 6, 7];
+/// The sides in the slice between the front and back faces.
+pub const STANDING_SIDES: [u8; 4] = [1, 3, 9, 11];
+
+/// Which sides are flipped when the top and bottom can only be turned
+/// twice.  They are all 0 in Thistlethwaite's G1.
+pub fn flips(cubies: &Cubies) -> [u8; 12] {
+    let mut flips = [0; 12];
+    for (slot, flip) in flips.iter_mut().enumerate() {
+        let piece = cubies.side_permutation[slot] as usize;
+        *flip = cubies.side_orientation[slot] ^ FLIP_REFERENCE[slot] ^ FLIP_REFERENCE[piece];
+    }
+    flips
+}
+
+/// How each corner is twisted, measured by its left or right color.
+/// They are all 0 in Thistlethwaite's G2.
+pub fn twists(cubies: &Cubies) -> [u8; 8] {
+    let mut twists = [0; 8];
+    for (slot, twist) in twists.iter_mut().enumerate() {
+        let piece = cubies.corner_permutation[slot] as usize;
+        *twist = (cubies.corner_orientation[slot] + TWIST_REFERENCE[piece] + 3 -
+                  TWIST_REFERENCE[slot]) % 3;
+    }
+    twists
+}
+
+/// The `permutation_index` of each corner permutation that half turns
+/// alone can make, sorted.
+pub fn half_turn_corners() -> &'static [u16] {
+    static HALF_TURN_CORNERS: OnceLock<Vec<u16>> = OnceLock::new();
+    HALF_TURN_CORNERS.get_or_init(|| {
+        let face_turns = cubies::face_turns();
+        let mut seen = vec![false; CORNER_PERMUTATIONS];
+        let mut half_turns = vec![0u16];
+        seen[0] = true;
+        let mut i = 0;
+        while i < half_turns.len() {
+            let mut permutation = [0; 8];
+            permutation_from_index(half_turns[i] as usize, &mut permutation);
+            // Every third of `FACE_TURNS`, starting from the second, is a
+            // half turn.
+            for turn in face_turns.iter().skip(1).step_by(3) {
+                let next: Vec<u8> = turn.corner_permutation.iter()
+                    .map(|&from| permutation[from as usize])
+                    .collect();
+                let index = permutation_index(&next);
+                if !seen[index] {
+                    seen[index] = true;
+                    half_turns.push(index as u16);
+                }
+            }
+            i += 1;
+        }
+        half_turns.sort();
+        half_turns
+    })
+}
+
+/// Is the cube in the group of Thistlethwaite's algorithm after `phase`
+/// phases?  Phase 0 is every cube, then come G1, G2 and G3, and phase 4
+/// is the solved cube.
+pub fn in_thistlethwaite_group(cubies: &Cubies, phase: usize) -> bool {
+    let sides_in = |slice: &[u8; 4]| {
+        slice.iter().all(|&s| slice.contains(&cubies.side_permutation[s as usize]))
+    };
+    let checks: [&dyn Fn() -> bool; 4] = [
+        &|| flips(cubies).iter().all(|&f| f == 0),
+        &|| twists(cubies).iter().all(|&t| t == 0) && sides_in(&MIDDLE_SIDES),
+        &|| half_turn_corners().binary_search(
+                &(permutation_index(&cubies.corner_permutation) as u16)).is_ok() &&
+            sides_in(&EQUATOR_SIDES),
+        &|| *cubies == Cubies::new(),
+    ];
+    checks[..phase].iter().all(|check| check())
+}
+
+impl Cubies {
+    pub fn to_coordinates(&self) -> Coordinates {
+        Coordinates {
+            corner_orientation: orientation_index(&self.corner_orientation, 3) as u16,
+            side_orientation: orientation_index(&self.side_orientation, 2) as u16,
+            corner_permutation: permutation_index(&self.corner_permutation) as u16,
+            side_permutation: permutation_index(&self.side_permutation) as u32,
+        }
+    }
+
+    pub fn from_coordinates(coordinates: &Coordinates) -> Result<Cubies, CoordinateError> {
+        if coordinates.corner_orientation as usize >= CORNER_ORIENTATIONS ||
+            coordinates.side_orientation as usize >= SIDE_ORIENTATIONS ||
+            coordinates.corner_permutation as usize >= CORNER_PERMUTATIONS ||
+            coordinates.side_permutation as usize >= SIDE_PERMUTATIONS {
+            return Err(CoordinateError::OutOfRange);
+        }
+        let mut cubies = Cubies::new();
+        orientation_from_index(coordinates.corner_orientation as usize, 3,
+                               &mut cubies.corner_orientation);
+        orientation_from_index(coordinates.side_orientation as usize, 2,
+                               &mut cubies.side_orientation);
+        permutation_from_index(coordinates.corner_permutation as usize,
+                               &mut cubies.corner_permutation);
+        permutation_from_index(coordinates.side_permutation as usize,
+                               &mut cubies.side_permutation);
+        Ok(cubies)
+    }
+}
+
+impl Cube {
+    /// Encode the cube's state relative to its centers.
+    pub fn to_coordinates(&self) -> Result<Coordinates, ValidationError> {
+        Ok(Cubies::from_cube(self)?.to_coordinates())
+    }
+
+    /// Build a cube held as `Cube::new` holds it.  This fails if the
+    /// corners and sides would have different parities.
+    pub fn from_coordinates(coordinates: &Coordinates) -> Result<Cube, CoordinateError> {
+        let cubies = Cubies::from_coordinates(coordinates)?;
+        cubies.validate().map_err(CoordinateError::Invalid)?;
+        Ok(cubies.to_cube(Cube::new().centers))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parser::parse;
+
+    #[test]
+    fn test_solved() {
+        assert_eq!(Ok(Coordinates {
+                       corner_orientation: 0,
+                       side_orientation: 0,
+                       corner_permutation: 0,
+                       side_permutation: 0,
+                   }),
+                   Cube::new().to_coordinates());
+    }
+
+    #[test]
+    fn test_permutation_index() {
+        assert_eq!(0, permutation_index(&[0, 1, 2, 3]));
+        assert_eq!(1, permutation_index(&[0, 1, 3, 2]));
+        assert_eq!(23, permutation_index(&[3, 2, 1, 0]));
+        for index in 0..120 {
+            let mut permutation = [0; 5];
+            permutation_from_index(index, &mut permutation);
+            assert_eq!(index, permutation_index(&permutation));
+        }
+    }
+
+    #[test]
+    fn test_orientation_index() {
+        assert_eq!(CORNER_ORIENTATIONS - 1, orientation_index(&[2, 2, 2, 2, 2, 2, 2, 0], 3));
+        assert_eq!(SIDE_ORIENTATIONS - 1, orientation_index(&[1; 12], 2));
+        for index in 0..CORNER_ORIENTATIONS {
+            let mut orientation = [0; 8];
+            orientation_from_index(index, 3, &mut orientation);
+            assert_eq!(0, orientation.iter().sum::<u8>() % 3);
+            assert_eq!(index, orientation_index(&orientation, 3));
+        }
+    }
+
+    #[test]
+    fn test_combination_index() {
+        assert_eq!(0, combination_index(&[true, true, false, false]));
+        assert_eq!(5, combination_index(&[false, false, true, true]));
+        for index in 0..binomial(12, 4) {
+            let mut chosen = [false; 12];
+            combination_from_index(index, 4, &mut chosen);
+            assert_eq!(4, chosen.iter().filter(|&&c| c).count());
+            assert_eq!(index, combination_index(&chosen));
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("R U2 F' L D B' R2 D2 F U'").unwrap());
+        let coordinates = cube.to_coordinates().unwrap();
+        assert!(coordinates.corner_orientation != 0);
+        assert!(coordinates.side_orientation != 0);
+        assert!(coordinates.corner_permutation != 0);
+        assert!(coordinates.side_permutation != 0);
+        assert_eq!(Ok(cube), Cube::from_coordinates(&coordinates));
+    }
+
+    #[test]
+    fn test_from_coordinates_errors() {
+        let coordinates = Coordinates {
+            corner_orientation: CORNER_ORIENTATIONS as u16,
+            side_orientation: 0,
+            corner_permutation: 0,
+            side_permutation: 0,
+        };
+        assert_eq!(Err(CoordinateError::OutOfRange), Cube::from_coordinates(&coordinates));
+
+        // Swapping two corners alone cannot be solved.
+        let coordinates = Coordinates {
+            corner_orientation: 0,
+            side_orientation: 0,
+            corner_permutation: 1,
+            side_permutation: 0,
+        };
+        assert_eq!(Err(CoordinateError::Invalid(ValidationError::Parity)),
+                   Cube::from_coordinates(&coordinates));
+    }
+
+    fn cubies(movements: &str) -> Cubies {
+        let mut cubies = Cubies::new();
+        for movement in &parse(movements).unwrap() {
+            assert!(cubies.apply(movement));
+        }
+        cubies
+    }
+
+    #[test]
+    fn test_flips() {
+        assert_eq!([0; 12], flips(&cubies("L R F B U2 D2 F' R2")));
+        assert_eq!([1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0], flips(&cubies("U")));
+    }
+
+    #[test]
+    fn test_twists() {
+        assert_eq!([0; 8], twists(&cubies("L R F2 B2 U2 D2 R'")));
+        assert!(twists(&cubies("F")).iter().any(|&t| t != 0));
+    }
+
+    #[test]
+    fn test_half_turn_corners() {
+        assert_eq!(96, half_turn_corners().len());
+        let index = permutation_index(&cubies("R2 U2 F2").corner_permutation) as u16;
+        assert!(half_turn_corners().binary_search(&index).is_ok());
+    }
+
+    #[test]
+    fn test_in_thistlethwaite_group() {
+        assert!(in_thistlethwaite_group(&cubies("L R F B U2 D2"), 1));
+        assert!(!in_thistlethwaite_group(&cubies("L R F B U2 D2"), 2));
+        assert!(in_thistlethwaite_group(&cubies("L R F2 B2 U2 D2"), 2));
+        assert!(!in_thistlethwaite_group(&cubies("L R F2 B2 U2 D2"), 3));
+        assert!(in_thistlethwaite_group(&cubies("L2 R2 F2 B2 U2 D2"), 3));
+        assert!(!in_thistlethwaite_group(&cubies("L2 R2 F2 B2 U2 D2"), 4));
+        assert!(!in_thistlethwaite_group(&cubies("U"), 1));
+    }
+}
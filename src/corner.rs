@@ -21,4 +21,11 @@ impl Corner {
     }
 
     pub fn color(&self, i: usize) -> Color { self.colors[i] }
+
+    /// The index of the color that is `top` or `bottom`.  A corner
+    /// sitting in the top or bottom layer has orientation 0 when that
+    /// color faces up or down, and turning it clockwise adds 1.
+    pub fn orientation(&self, top: Color, bottom: Color) -> Option<u8> {
+        self.colors.iter().position(|&c| c == top || c == bottom).map(|i| i as u8)
+    }
 }
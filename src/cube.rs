@@ -5,12 +5,30 @@ use rand;
 use rand::Rng;
 use side::Side;
 use corner::Corner;
+use movement::{self, Direction, Times, Movement, FACE_TURNS};
+
+/// Index of the front center in `Cube::centers`.
+pub const FRONT: usize = 0;
+/// Index of the back center in `Cube::centers`.
+pub const BACK: usize = 1;
+/// Index of the left center in `Cube::centers`.
+pub const LEFT: usize = 2;
+/// Index of the right center in `Cube::centers`.
+pub const RIGHT: usize = 3;
+/// Index of the top center in `Cube::centers`.
+pub const TOP: usize = 4;
+/// Index of the bottom center in `Cube::centers`.
+pub const BOTTOM: usize = 5;
 
 /// A Rubick's cube
 ///
-/// We define the front as white and the bottom as orange.
-#[derive(Debug, PartialEq, Eq)]
+/// A new cube has white in the front and orange on the bottom.  Slice
+/// moves carry the centers with them, so this may change.
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Cube {
+    /// The colors of the centers, indexed by `FRONT`, `BACK`, `LEFT`,
+    /// `RIGHT`, `TOP` and `BOTTOM`.
+    pub centers: [Color; 6],
     /// Start on the bottom on the face (OW) and go clockwise _on the
     /// bottom_.  It is as if we look from below the cube.
     pub bottom_sides: [Side; 4],
@@ -31,117 +49,288 @@ pub struct Cube {
 
 impl fmt::Display for Cube {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "          {} {} {}
-
-          {} {} {}
-          {} {} {}
-          {} {} {}
-
-{}  {} {} {}  {} {} {}  {} {} {}  {}
-{}  {} {} {}  {} {} {}  {} {} {}  {}
-{}  {} {} {}  {} {} {}  {} {} {}  {}
-
-          {} {} {}
-          {} {} {}
-          {} {} {}
-
-          {} {} {}",
-               self.top_corners[1].color(2), self.top_sides[2].color(1), self.top_corners[2].color(1),
-               
-               self.top_corners[1].color(0), self.top_sides[2].color(0), self.top_corners[2].color(0),
-               self.top_sides[1].color(0), Color::Red, self.top_sides[3].color(0),
-               self.top_corners[0].color(0), self.top_sides[0].color(0), self.top_corners[3].color(0),
-
-               self.top_corners[1].color(2),
-               self.top_corners[1].color(1), self.top_sides[1].color(1), self.top_corners[0].color(2),
-               self.top_corners[0].color(1), self.top_sides[0].color(1), self.top_corners[3].color(2),
-               self.top_corners[3].color(1), self.top_sides[3].color(1), self.top_corners[2].color(2),
-               self.top_corners[2].color(1),
-
-               self.middle_sides[1].color(1),
-               self.middle_sides[1].color(0), Color::Blue, self.middle_sides[0].color(1),
-               self.middle_sides[0].color(0), Color::White, self.middle_sides[3].color(1),
-               self.middle_sides[3].color(0), Color::Green, self.middle_sides[2].color(1),
-               self.middle_sides[2].color(0),
-
-               self.bottom_corners[3].color(1),
-               self.bottom_corners[3].color(2), self.bottom_sides[3].color(1), self.bottom_corners[0].color(1),
-               self.bottom_corners[0].color(2), self.bottom_sides[0].color(1), self.bottom_corners[1].color(1),
-               self.bottom_corners[1].color(2), self.bottom_sides[1].color(1), self.bottom_corners[2].color(1),
-               self.bottom_corners[2].color(2),
-
-               self.bottom_corners[0].color(0), self.bottom_sides[0].color(0), self.bottom_corners[1].color(0),
-               self.bottom_sides[3].color(0), Color::Orange, self.bottom_sides[1].color(0),
-               self.bottom_corners[3].color(0), self.bottom_sides[2].color(0), self.bottom_corners[2].color(0),
-
-               self.bottom_corners[3].color(1), self.bottom_sides[2].color(1), self.bottom_corners[2].color(2),
-        )
+        self.write_net(fmt, |cell| match cell {
+            Some(color) => color.to_string(),
+            None => " ".to_string(),
+        })
+    }
+}
+
+/// Displays a cube as `Cube`'s `Display` impl does, but draws each
+/// sticker as a block of its color using ANSI truecolor escapes.
+pub struct Colored<'a>(&'a Cube);
+
+impl<'a> fmt::Display for Colored<'a> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.0.write_net(fmt, |cell| match cell {
+            Some(color) => {
+                let (r, g, b) = color.rgb();
+                format!("\x1b[48;2;{};{};{}m  \x1b[0m", r, g, b)
+            },
+            None => "  ".to_string(),
+        })
+    }
+}
+
+impl Default for Cube {
+    fn default() -> Self {
+        Cube::new()
     }
 }
 
 impl Cube {
+    /// The stickers drawn by `Display`, in rows of eleven cells: one
+    /// sticker of the back, the left, front and right faces, and another
+    /// sticker of the back.  The top, bottom and the strips of the back
+    /// above and below them are drawn over the front.
+    fn net(&self) -> [[Option<Color>; 11]; 15] {
+        let strip = |colors: [Color; 3]| {
+            let mut row = [None; 11];
+            for (cell, &color) in row[4..7].iter_mut().zip(&colors) {
+                *cell = Some(color);
+            }
+            row
+        };
+        let row = |colors: [Color; 11]| colors.map(Some);
+        let (tc, ts) = (&self.top_corners, &self.top_sides);
+        let (ms, c) = (&self.middle_sides, &self.centers);
+        let (bc, bs) = (&self.bottom_corners, &self.bottom_sides);
+        [
+            strip([tc[1].color(2), ts[2].color(1), tc[2].color(1)]),
+            [None; 11],
+            strip([tc[1].color(0), ts[2].color(0), tc[2].color(0)]),
+            strip([ts[1].color(0), c[TOP], ts[3].color(0)]),
+            strip([tc[0].color(0), ts[0].color(0), tc[3].color(0)]),
+            [None; 11],
+            row([tc[1].color(2),
+                 tc[1].color(1), ts[1].color(1), tc[0].color(2),
+                 tc[0].color(1), ts[0].color(1), tc[3].color(2),
+                 tc[3].color(1), ts[3].color(1), tc[2].color(2),
+                 tc[2].color(1)]),
+            row([ms[1].color(1),
+                 ms[1].color(0), c[LEFT], ms[0].color(1),
+                 ms[0].color(0), c[FRONT], ms[3].color(1),
+                 ms[3].color(0), c[RIGHT], ms[2].color(1),
+                 ms[2].color(0)]),
+            row([bc[3].color(1),
+                 bc[3].color(2), bs[3].color(1), bc[0].color(1),
+                 bc[0].color(2), bs[0].color(1), bc[1].color(1),
+                 bc[1].color(2), bs[1].color(1), bc[2].color(1),
+                 bc[2].color(2)]),
+            [None; 11],
+            strip([bc[0].color(0), bs[0].color(0), bc[1].color(0)]),
+            strip([bs[3].color(0), c[BOTTOM], bs[1].color(0)]),
+            strip([bc[3].color(0), bs[2].color(0), bc[2].color(0)]),
+            [None; 11],
+            strip([bc[3].color(1), bs[2].color(1), bc[2].color(2)]),
+        ]
+    }
+
+    /// Write `net` a row to a line, drawing each cell with `sticker`.
+    /// Faces are spaced further apart than the stickers on them.
+    fn write_net<F>(&self, fmt: &mut fmt::Formatter, sticker: F) -> fmt::Result
+        where F: Fn(Option<Color>) -> String {
+        for (i, cells) in self.net().iter().enumerate() {
+            let mut line = String::new();
+            for (column, &cell) in cells.iter().enumerate() {
+                match column {
+                    0 => (),
+                    1 | 4 | 7 | 10 => line.push_str("  "),
+                    _ => line.push(' '),
+                }
+                line.push_str(&sticker(cell));
+            }
+            if i > 0 {
+                writeln!(fmt)?;
+            }
+            write!(fmt, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+
+    /// Display the cube in color, as `Colored` does.
+    pub fn colored(&self) -> Colored<'_> {
+        Colored(self)
+    }
+
     pub fn new() -> Self {
+        Cube::solved_with_centers([Color::White, Color::Yellow, Color::Blue,
+                                   Color::Green, Color::Red, Color::Orange])
+    }
+
+    /// A solved cube held with the given centers.
+    pub fn solved_with_centers(c: [Color; 6]) -> Self {
         Cube {
+            centers: c,
             bottom_sides: [
-                Side::new(Color::Orange, Color::White),
-                Side::new(Color::Orange, Color::Green),
-                Side::new(Color::Orange, Color::Yellow),
-                Side::new(Color::Orange, Color::Blue),
+                Side::new(c[BOTTOM], c[FRONT]),
+                Side::new(c[BOTTOM], c[RIGHT]),
+                Side::new(c[BOTTOM], c[BACK]),
+                Side::new(c[BOTTOM], c[LEFT]),
             ],
             bottom_corners: [
-                Corner::new(Color::Orange, Color::Blue, Color::White),
-                Corner::new(Color::Orange, Color::White, Color::Green),
-                Corner::new(Color::Orange, Color::Green, Color::Yellow),
-                Corner::new(Color::Orange, Color::Yellow, Color::Blue),
+                Corner::new(c[BOTTOM], c[LEFT], c[FRONT]),
+                Corner::new(c[BOTTOM], c[FRONT], c[RIGHT]),
+                Corner::new(c[BOTTOM], c[RIGHT], c[BACK]),
+                Corner::new(c[BOTTOM], c[BACK], c[LEFT]),
             ],
             middle_sides: [
-                Side::new(Color::White, Color::Blue),
-                Side::new(Color::Blue, Color::Yellow),
-                Side::new(Color::Yellow, Color::Green),
-                Side::new(Color::Green, Color::White),
+                Side::new(c[FRONT], c[LEFT]),
+                Side::new(c[LEFT], c[BACK]),
+                Side::new(c[BACK], c[RIGHT]),
+                Side::new(c[RIGHT], c[FRONT]),
             ],
             top_sides: [
-                Side::new(Color::Red, Color::White),
-                Side::new(Color::Red, Color::Blue),
-                Side::new(Color::Red, Color::Yellow),
-                Side::new(Color::Red, Color::Green),
+                Side::new(c[TOP], c[FRONT]),
+                Side::new(c[TOP], c[LEFT]),
+                Side::new(c[TOP], c[BACK]),
+                Side::new(c[TOP], c[RIGHT]),
             ],
             top_corners: [
-                Corner::new(Color::Red, Color::White, Color::Blue),
-                Corner::new(Color::Red, Color::Blue, Color::Yellow),
-                Corner::new(Color::Red, Color::Yellow, Color::Green),
-                Corner::new(Color::Red, Color::Green, Color::White),
+                Corner::new(c[TOP], c[FRONT], c[LEFT]),
+                Corner::new(c[TOP], c[LEFT], c[BACK]),
+                Corner::new(c[TOP], c[BACK], c[RIGHT]),
+                Corner::new(c[TOP], c[RIGHT], c[FRONT]),
             ],
         }
     }
 
-    fn rotate_random_(&mut self, i: i32) {
-        match i {
-            0 => self.rotate_front_clockwise(),
-            1 => self.rotate_front_counter_clockwise(),
-            2 => self.rotate_right_clockwise(),
-            3 => self.rotate_right_counter_clockwise(),
-            4 => self.rotate_top_clockwise(),
-            5 => self.rotate_top_counter_clockwise(),
-            6 => self.rotate_left_clockwise(),
-            7 => self.rotate_left_counter_clockwise(),
-            8 => self.rotate_bottom_clockwise(),
-            9 => self.rotate_bottom_counter_clockwise(),
-            10 => self.rotate_back_clockwise(),
-            11 => self.rotate_back_counter_clockwise(),
-            _ => unreachable!(),
+    /// Is the cube solved with white in the front and orange on the
+    /// bottom, as `Cube::new` makes it?
+    pub fn is_solved(&self) -> bool {
+        *self == Cube::new()
+    }
+
+    /// Is the cube solved no matter how it is held?  Every piece must
+    /// match the centers around it.
+    pub fn is_solved_in_any_orientation(&self) -> bool {
+        *self == Cube::solved_with_centers(self.centers)
+    }
+
+    /// Apply a single movement to the cube.
+    pub fn apply(&mut self, movement: &Movement) {
+        match movement.times {
+            Times::Clockwise => self.rotate_clockwise(&movement.direction),
+            Times::CounterClockwise => self.rotate_counter_clockwise(&movement.direction),
+            Times::Double => {
+                self.rotate_clockwise(&movement.direction);
+                self.rotate_clockwise(&movement.direction);
+            },
+        }
+    }
+
+    /// Apply each movement in order.
+    pub fn apply_all(&mut self, movements: &[Movement]) {
+        for movement in movements {
+            self.apply(movement);
+        }
+    }
+
+    fn rotate_clockwise(&mut self, direction: &Direction) {
+        match *direction {
+            Direction::Front => self.rotate_front_clockwise(),
+            Direction::Back => self.rotate_back_clockwise(),
+            Direction::Left => self.rotate_left_clockwise(),
+            Direction::Right => self.rotate_right_clockwise(),
+            Direction::Top => self.rotate_top_clockwise(),
+            Direction::Bottom => self.rotate_bottom_clockwise(),
+            Direction::Middle => self.rotate_middle_clockwise(),
+            Direction::Equator => self.rotate_equator_clockwise(),
+            Direction::Standing => self.rotate_standing_clockwise(),
+            Direction::WideFront => {
+                self.rotate_front_clockwise();
+                self.rotate_standing_clockwise();
+            },
+            Direction::WideBack => {
+                self.rotate_back_clockwise();
+                self.rotate_standing_counter_clockwise();
+            },
+            Direction::WideLeft => {
+                self.rotate_left_clockwise();
+                self.rotate_middle_clockwise();
+            },
+            Direction::WideRight => {
+                self.rotate_right_clockwise();
+                self.rotate_middle_counter_clockwise();
+            },
+            Direction::WideTop => {
+                self.rotate_top_clockwise();
+                self.rotate_equator_counter_clockwise();
+            },
+            Direction::WideBottom => {
+                self.rotate_bottom_clockwise();
+                self.rotate_equator_clockwise();
+            },
+            Direction::X => self.rotate_x_clockwise(),
+            Direction::Y => self.rotate_y_clockwise(),
+            Direction::Z => self.rotate_z_clockwise(),
         }
     }
 
-    pub fn shuffle(&mut self) {
-        let mut rand = rand::thread_rng();
-        for _ in 0..rand.gen_range(15, 21) {
-            self.rotate_random_(rand.gen_range(0, 12));
+    fn rotate_counter_clockwise(&mut self, direction: &Direction) {
+        match *direction {
+            Direction::Front => self.rotate_front_counter_clockwise(),
+            Direction::Back => self.rotate_back_counter_clockwise(),
+            Direction::Left => self.rotate_left_counter_clockwise(),
+            Direction::Right => self.rotate_right_counter_clockwise(),
+            Direction::Top => self.rotate_top_counter_clockwise(),
+            Direction::Bottom => self.rotate_bottom_counter_clockwise(),
+            Direction::Middle => self.rotate_middle_counter_clockwise(),
+            Direction::Equator => self.rotate_equator_counter_clockwise(),
+            Direction::Standing => self.rotate_standing_counter_clockwise(),
+            Direction::WideFront => {
+                self.rotate_front_counter_clockwise();
+                self.rotate_standing_counter_clockwise();
+            },
+            Direction::WideBack => {
+                self.rotate_back_counter_clockwise();
+                self.rotate_standing_clockwise();
+            },
+            Direction::WideLeft => {
+                self.rotate_left_counter_clockwise();
+                self.rotate_middle_counter_clockwise();
+            },
+            Direction::WideRight => {
+                self.rotate_right_counter_clockwise();
+                self.rotate_middle_clockwise();
+            },
+            Direction::WideTop => {
+                self.rotate_top_counter_clockwise();
+                self.rotate_equator_clockwise();
+            },
+            Direction::WideBottom => {
+                self.rotate_bottom_counter_clockwise();
+                self.rotate_equator_counter_clockwise();
+            },
+            Direction::X => self.rotate_x_counter_clockwise(),
+            Direction::Y => self.rotate_y_counter_clockwise(),
+            Direction::Z => self.rotate_z_counter_clockwise(),
         }
     }
 
-    pub fn rotate_random(&mut self) {
-        self.rotate_random_(rand::thread_rng().gen_range(0, 12));
+    /// Make a random face turn and return it.
+    pub fn rotate_random_with<R: Rng>(&mut self, rng: &mut R) -> Movement {
+        let movement = FACE_TURNS[rng.gen_range(0, FACE_TURNS.len())];
+        self.apply(&movement);
+        movement
+    }
+
+    pub fn rotate_random(&mut self) -> Movement {
+        self.rotate_random_with(&mut rand::thread_rng())
+    }
+
+    /// Make 15 to 20 random face turns, as `movement::random_turns`
+    /// makes them, and return them.  The same generator state always
+    /// gives the same turns.
+    pub fn shuffle_with<R: Rng>(&mut self, rng: &mut R) -> Vec<Movement> {
+        let length = rng.gen_range(15, 21);
+        let movements = movement::random_turns(rng, length);
+        self.apply_all(&movements);
+        movements
+    }
+
+    pub fn shuffle(&mut self) -> Vec<Movement> {
+        self.shuffle_with(&mut rand::thread_rng())
     }
 
     pub fn rotate_right_clockwise(&mut self) {
@@ -355,6 +544,143 @@ impl Cube {
         mem::swap(&mut self.middle_sides[1], &mut self.bottom_sides[2]);
         mem::swap(&mut self.bottom_sides[2], &mut self.middle_sides[2]);
     }
+
+    /// Turn the slice between the left and right faces the same way
+    /// as the left face.
+    pub fn rotate_middle_clockwise(&mut self) {
+        // a, b
+        self.top_sides.swap(0, 2);
+        // b, c
+        mem::swap(&mut self.top_sides[2], &mut self.bottom_sides[2]);
+        // c, d
+        self.bottom_sides.swap(2, 0);
+
+        self.centers.swap(FRONT, TOP);
+        self.centers.swap(TOP, BACK);
+        self.centers.swap(BACK, BOTTOM);
+
+        self.top_sides[0].flip();
+        self.top_sides[2].flip();
+        self.bottom_sides[0].flip();
+        self.bottom_sides[2].flip();
+    }
+
+    pub fn rotate_middle_counter_clockwise(&mut self) {
+        // a, b
+        mem::swap(&mut self.top_sides[0], &mut self.bottom_sides[0]);
+        // b, c
+        self.bottom_sides.swap(0, 2);
+        // c, d
+        mem::swap(&mut self.bottom_sides[2], &mut self.top_sides[2]);
+
+        self.centers.swap(FRONT, BOTTOM);
+        self.centers.swap(BOTTOM, BACK);
+        self.centers.swap(BACK, TOP);
+
+        self.top_sides[0].flip();
+        self.top_sides[2].flip();
+        self.bottom_sides[0].flip();
+        self.bottom_sides[2].flip();
+    }
+
+    /// Turn the slice between the top and bottom faces the same way
+    /// as the bottom face.
+    pub fn rotate_equator_clockwise(&mut self) {
+        self.middle_sides.swap(0, 1);
+        self.middle_sides.swap(1, 2);
+        self.middle_sides.swap(2, 3);
+
+        self.centers.swap(FRONT, LEFT);
+        self.centers.swap(LEFT, BACK);
+        self.centers.swap(BACK, RIGHT);
+    }
+
+    pub fn rotate_equator_counter_clockwise(&mut self) {
+        self.middle_sides.swap(0, 3);
+        self.middle_sides.swap(3, 2);
+        self.middle_sides.swap(2, 1);
+
+        self.centers.swap(FRONT, RIGHT);
+        self.centers.swap(RIGHT, BACK);
+        self.centers.swap(BACK, LEFT);
+    }
+
+    /// Turn the slice between the front and back faces the same way
+    /// as the front face.
+    pub fn rotate_standing_clockwise(&mut self) {
+        // a, b
+        mem::swap(&mut self.top_sides[1], &mut self.bottom_sides[3]);
+        // b, c
+        self.bottom_sides.swap(3, 1);
+        // c, d
+        mem::swap(&mut self.bottom_sides[1], &mut self.top_sides[3]);
+
+        self.centers.swap(TOP, LEFT);
+        self.centers.swap(LEFT, BOTTOM);
+        self.centers.swap(BOTTOM, RIGHT);
+
+        self.top_sides[1].flip();
+        self.top_sides[3].flip();
+        self.bottom_sides[1].flip();
+        self.bottom_sides[3].flip();
+    }
+
+    pub fn rotate_standing_counter_clockwise(&mut self) {
+        // a, b
+        self.top_sides.swap(1, 3);
+        // b, c
+        mem::swap(&mut self.top_sides[3], &mut self.bottom_sides[1]);
+        // c, d
+        self.bottom_sides.swap(1, 3);
+
+        self.centers.swap(TOP, RIGHT);
+        self.centers.swap(RIGHT, BOTTOM);
+        self.centers.swap(BOTTOM, LEFT);
+
+        self.top_sides[1].flip();
+        self.top_sides[3].flip();
+        self.bottom_sides[1].flip();
+        self.bottom_sides[3].flip();
+    }
+
+    /// Rotate the whole cube the same way as the right face.
+    pub fn rotate_x_clockwise(&mut self) {
+        self.rotate_right_clockwise();
+        self.rotate_middle_counter_clockwise();
+        self.rotate_left_counter_clockwise();
+    }
+
+    pub fn rotate_x_counter_clockwise(&mut self) {
+        self.rotate_right_counter_clockwise();
+        self.rotate_middle_clockwise();
+        self.rotate_left_clockwise();
+    }
+
+    /// Rotate the whole cube the same way as the top face.
+    pub fn rotate_y_clockwise(&mut self) {
+        self.rotate_top_clockwise();
+        self.rotate_equator_counter_clockwise();
+        self.rotate_bottom_counter_clockwise();
+    }
+
+    pub fn rotate_y_counter_clockwise(&mut self) {
+        self.rotate_top_counter_clockwise();
+        self.rotate_equator_clockwise();
+        self.rotate_bottom_clockwise();
+    }
+
+    /// Rotate the whole cube the same way as the front face.
+    pub fn rotate_z_clockwise(&mut self) {
+        self.rotate_front_clockwise();
+        self.rotate_standing_clockwise();
+        self.rotate_back_counter_clockwise();
+    }
+
+    pub fn rotate_z_counter_clockwise(&mut self) {
+        self.rotate_front_counter_clockwise();
+        self.rotate_standing_counter_clockwise();
+        self.rotate_back_clockwise();
+    }
 }
 
 #[cfg(test)]
@@ -366,7 +692,34 @@ mod test {
         assert_is_solved(&Cube::new());
     }
 
+    #[test]
+    fn test_is_solved() {
+        let mut cube = Cube::new();
+        assert!(cube.is_solved());
+        assert!(cube.is_solved_in_any_orientation());
+
+        cube.rotate_right_clockwise();
+        assert!(!cube.is_solved());
+        assert!(!cube.is_solved_in_any_orientation());
+        cube.rotate_right_counter_clockwise();
+
+        cube.rotate_x_clockwise();
+        cube.rotate_y_counter_clockwise();
+        assert!(!cube.is_solved());
+        assert!(cube.is_solved_in_any_orientation());
+
+        cube.rotate_middle_clockwise();
+        assert!(!cube.is_solved_in_any_orientation());
+        cube.rotate_right_counter_clockwise();
+        cube.rotate_left_clockwise();
+        assert!(!cube.is_solved());
+        assert!(cube.is_solved_in_any_orientation());
+    }
+
     fn assert_is_solved(cube: &Cube) {
+        assert_eq!(cube.centers,
+                   [Color::White, Color::Yellow, Color::Blue,
+                    Color::Green, Color::Red, Color::Orange]);
         assert_eq!(cube.bottom_sides,
                    [
                        Side::new(Color::Orange, Color::White),
@@ -698,6 +1051,45 @@ mod test {
         assert_is_solved(&cube);
     }
 
+    #[test]
+    fn test_apply() {
+        let mut cube = Cube::new();
+        let mut expected = Cube::new();
+        cube.apply(&Movement::new(Direction::Right, Times::Clockwise));
+        expected.rotate_right_clockwise();
+        assert_eq!(expected, cube);
+
+        cube.apply(&Movement::new(Direction::Front, Times::CounterClockwise));
+        expected.rotate_front_counter_clockwise();
+        assert_eq!(expected, cube);
+
+        cube.apply(&Movement::new(Direction::Top, Times::Double));
+        expected.rotate_top_clockwise();
+        expected.rotate_top_clockwise();
+        assert_eq!(expected, cube);
+    }
+
+    #[test]
+    fn test_apply_all() {
+        let directions = [Direction::Front, Direction::Back, Direction::Left,
+                          Direction::Right, Direction::Top, Direction::Bottom,
+                          Direction::Middle, Direction::Equator, Direction::Standing,
+                          Direction::WideFront, Direction::WideBack, Direction::WideLeft,
+                          Direction::WideRight, Direction::WideTop, Direction::WideBottom,
+                          Direction::X, Direction::Y, Direction::Z];
+        for direction in directions.iter() {
+            let mut cube = Cube::new();
+            cube.apply_all(&[Movement::new(*direction, Times::Clockwise),
+                             Movement::new(*direction, Times::Double),
+                             Movement::new(*direction, Times::Clockwise)]);
+            assert_is_solved(&cube);
+
+            cube.apply_all(&[Movement::new(*direction, Times::Clockwise),
+                             Movement::new(*direction, Times::CounterClockwise)]);
+            assert_is_solved(&cube);
+        }
+    }
+
     #[test]
     fn test_display() {
         let mut cube = Cube::new();
@@ -776,4 +1168,216 @@ G  O O O  B W O  G G G  R
           G Y R",
                    format!("\n{}", cube));
     }
+
+    #[test]
+    fn test_display_slices() {
+        let mut cube = Cube::new();
+        cube.rotate_middle_clockwise();
+        assert_eq!("
+          Y O Y
+
+          R Y R
+          R Y R
+          R Y R
+
+Y  B B B  W R W  G G G  Y
+Y  B B B  W R W  G G G  Y
+Y  B B B  W R W  G G G  Y
+
+          O W O
+          O W O
+          O W O
+
+          Y O Y",
+                   format!("\n{}", cube));
+
+        let mut cube = Cube::new();
+        cube.rotate_equator_clockwise();
+        assert_eq!("
+          Y Y Y
+
+          R R R
+          R R R
+          R R R
+
+Y  B B B  W W W  G G G  Y
+G  Y Y Y  B B B  W W W  G
+Y  B B B  W W W  G G G  Y
+
+          O O O
+          O O O
+          O O O
+
+          Y Y Y",
+                   format!("\n{}", cube));
+
+        let mut cube = Cube::new();
+        cube.rotate_standing_clockwise();
+        assert_eq!("
+          Y Y Y
+
+          R R R
+          B B B
+          R R R
+
+Y  B O B  W W W  G R G  Y
+Y  B O B  W W W  G R G  Y
+Y  B O B  W W W  G R G  Y
+
+          O O O
+          G G G
+          O O O
+
+          Y Y Y",
+                   format!("\n{}", cube));
+
+        cube.rotate_standing_counter_clockwise();
+        cube.rotate_middle_clockwise();
+        cube.rotate_top_clockwise();
+        cube.rotate_middle_counter_clockwise();
+        assert_eq!("
+          B R B
+
+          R G R
+          Y R Y
+          R R R
+
+B  W R W  G W G  Y O Y  B
+Y  B B B  W W W  G G G  Y
+Y  B B B  W W W  G G G  Y
+
+          O O O
+          O O O
+          O B O
+
+          Y R Y",
+                   format!("\n{}", cube));
+    }
+
+    #[test]
+    fn test_rotations() {
+        let mut cube = Cube::new();
+        cube.rotate_x_clockwise();
+        assert_eq!(cube.centers,
+                   [Color::Orange, Color::Red, Color::Blue,
+                    Color::Green, Color::White, Color::Yellow]);
+
+        let mut cube = Cube::new();
+        cube.rotate_y_clockwise();
+        assert_eq!(cube.centers,
+                   [Color::Green, Color::Blue, Color::White,
+                    Color::Yellow, Color::Red, Color::Orange]);
+
+        let mut cube = Cube::new();
+        cube.rotate_z_clockwise();
+        assert_eq!(cube.centers,
+                   [Color::White, Color::Yellow, Color::Orange,
+                    Color::Red, Color::Blue, Color::Green]);
+        assert_eq!("
+          Y Y Y
+
+          B B B
+          B B B
+          B B B
+
+Y  O O O  W W W  R R R  Y
+Y  O O O  W W W  R R R  Y
+Y  O O O  W W W  R R R  Y
+
+          G G G
+          G G G
+          G G G
+
+          Y Y Y",
+                   format!("\n{}", cube));
+    }
+
+    #[test]
+    fn test_rotations_reorient_faces() {
+        // Rotating, turning and rotating back turns a different face.
+        let mut cube = Cube::new();
+        cube.rotate_x_clockwise();
+        cube.rotate_top_clockwise();
+        cube.rotate_x_counter_clockwise();
+        let mut expected = Cube::new();
+        expected.rotate_front_clockwise();
+        assert_eq!(expected, cube);
+
+        let mut cube = Cube::new();
+        cube.rotate_y_clockwise();
+        cube.rotate_right_clockwise();
+        cube.rotate_y_counter_clockwise();
+        let mut expected = Cube::new();
+        expected.rotate_back_clockwise();
+        assert_eq!(expected, cube);
+
+        let mut cube = Cube::new();
+        cube.rotate_z_clockwise();
+        cube.rotate_top_clockwise();
+        cube.rotate_z_counter_clockwise();
+        let mut expected = Cube::new();
+        expected.rotate_left_clockwise();
+        assert_eq!(expected, cube);
+
+        // M is the same as R L' followed by x'.
+        let mut cube = Cube::new();
+        cube.rotate_middle_clockwise();
+        let mut expected = Cube::new();
+        expected.rotate_right_clockwise();
+        expected.rotate_left_counter_clockwise();
+        expected.rotate_x_counter_clockwise();
+        assert_eq!(expected, cube);
+    }
+
+    #[test]
+    fn test_wide() {
+        // A wide move turns the opposite face and rotates the cube.
+        let pairs = [(Direction::WideRight, Direction::Left, Direction::X),
+                     (Direction::WideLeft, Direction::Right, Direction::X),
+                     (Direction::WideTop, Direction::Bottom, Direction::Y),
+                     (Direction::WideBottom, Direction::Top, Direction::Y),
+                     (Direction::WideFront, Direction::Back, Direction::Z),
+                     (Direction::WideBack, Direction::Front, Direction::Z)];
+        for &(wide, face, rotation) in pairs.iter() {
+            let rotation_times = match wide {
+                Direction::WideRight | Direction::WideTop | Direction::WideFront =>
+                    Times::Clockwise,
+                _ => Times::CounterClockwise,
+            };
+            let mut cube = Cube::new();
+            cube.apply(&Movement::new(wide, Times::Clockwise));
+            let mut expected = Cube::new();
+            expected.apply_all(&[Movement::new(face, Times::Clockwise),
+                                 Movement::new(rotation, rotation_times)]);
+            assert_eq!(expected, cube);
+        }
+    }
+
+    #[test]
+    fn test_shuffle_with() {
+        use rand::{SeedableRng, StdRng};
+        let mut cube = Cube::new();
+        let movements = cube.shuffle_with(&mut StdRng::from_seed(&[3][..]));
+        assert!(movements.len() >= 15 && movements.len() <= 20);
+        let mut replay = Cube::new();
+        replay.apply_all(&movements);
+        assert_eq!(cube, replay);
+        assert_eq!(movements, Cube::new().shuffle_with(&mut StdRng::from_seed(&[3][..])));
+
+        let mut cube = Cube::new();
+        let movement = cube.rotate_random_with(&mut StdRng::from_seed(&[3][..]));
+        assert!(FACE_TURNS.contains(&movement));
+        assert!(!cube.is_solved());
+    }
+
+    #[test]
+    fn test_colored() {
+        let cube = Cube::new();
+        let colored = cube.colored().to_string();
+        assert_eq!(cube.to_string().lines().count(), colored.lines().count());
+        assert_eq!(57, colored.matches("\x1b[0m").count());
+        let first = colored.lines().next().unwrap();
+        assert!(first.starts_with("              \x1b[48;2;255;213;0m  \x1b[0m"));
+        assert!(colored.lines().all(|line| line == line.trim_end()));
+    }
 }
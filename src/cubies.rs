@@ -0,0 +1,332 @@
+use std::sync::OnceLock;
+use color::Color;
+use corner::Corner;
+use cube::{Cube, FRONT, BACK, TOP, BOTTOM};
+use movement::{Movement, FACE_TURNS};
+use side::Side;
+use validation::ValidationError;
+
+/// For each side slot, the index of the color that decides whether
+/// the side is flipped.  This is the color on the top or bottom, or
+/// failing that the one on the front or back.
+const SIDE_REFERENCE: [usize; 12] = [0, 0, 0, 0, 0, 1, 0, 1, 0, 0, 0, 0];
+
+/// The pieces of a cube, described by which piece is in each slot and
+/// how it is turned relative to the centers.
+///
+/// Corner slots are the `top_corners` followed by the
+/// `bottom_corners`.  Side slots are the `top_sides`, then the
+/// `middle_sides`, then the `bottom_sides`.  A piece is numbered by
+/// the slot it belongs in when the cube is solved.
+///
+/// A corner's orientation is the index of its top or bottom color.  A
+/// side's orientation is 1 if it is flipped: its top or bottom color
+/// (or front or back color for the middle sides) is not on the top,
+/// bottom, front or back of the slot as it would be when solved.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Cubies {
+    pub corner_permutation: [u8; 8],
+    pub corner_orientation: [u8; 8],
+    pub side_permutation: [u8; 12],
+    pub side_orientation: [u8; 12],
+}
+
+pub fn corner(cube: &Cube, slot: usize) -> &Corner {
+    if slot < 4 {
+        &cube.top_corners[slot]
+    } else {
+        &cube.bottom_corners[slot - 4]
+    }
+}
+
+fn corner_mut(cube: &mut Cube, slot: usize) -> &mut Corner {
+    if slot < 4 {
+        &mut cube.top_corners[slot]
+    } else {
+        &mut cube.bottom_corners[slot - 4]
+    }
+}
+
+pub fn side(cube: &Cube, slot: usize) -> &Side {
+    match slot / 4 {
+        0 => &cube.top_sides[slot % 4],
+        1 => &cube.middle_sides[slot % 4],
+        _ => &cube.bottom_sides[slot % 4],
+    }
+}
+
+fn side_mut(cube: &mut Cube, slot: usize) -> &mut Side {
+    match slot / 4 {
+        0 => &mut cube.top_sides[slot % 4],
+        1 => &mut cube.middle_sides[slot % 4],
+        _ => &mut cube.bottom_sides[slot % 4],
+    }
+}
+
+/// Is `piece` the same as `home`, perhaps turned?
+fn same_corner(piece: &Corner, home: &Corner) -> bool {
+    (0..3).any(|o| (0..3).all(|i| piece.color((i + o) % 3) == home.color(i)))
+}
+
+fn same_side(piece: &Side, home: &Side) -> bool {
+    (piece.color(0) == home.color(0) && piece.color(1) == home.color(1)) ||
+        (piece.color(0) == home.color(1) && piece.color(1) == home.color(0))
+}
+
+impl Cubies {
+    /// The cubies of a solved cube.
+    pub fn new() -> Self {
+        Cubies {
+            corner_permutation: [0, 1, 2, 3, 4, 5, 6, 7],
+            corner_orientation: [0; 8],
+            side_permutation: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+            side_orientation: [0; 12],
+        }
+    }
+
+    /// Find each piece of `cube` relative to its centers.  This fails
+    /// if the centers are not those of a real cube or if the pieces do
+    /// not match the ones a solved cube would have.
+    pub fn from_cube(cube: &Cube) -> Result<Self, ValidationError> {
+        if !valid_centers(&cube.centers) {
+            return Err(ValidationError::InvalidCenters);
+        }
+        let c = &cube.centers;
+        let solved = Cube::solved_with_centers(*c);
+        let mut cubies = Cubies::new();
+        let mut seen_corners = [None; 8];
+        for slot in 0..8 {
+            let piece = corner(cube, slot);
+            let home = (0..8)
+                .find(|&home| same_corner(piece, corner(&solved, home)))
+                .ok_or_else(|| ValidationError::UnknownCorner(piece.clone()))?;
+            if seen_corners[home].is_some() {
+                let missing = (0..8).find(|&h| {
+                    !(0..8).any(|s| same_corner(corner(cube, s), corner(&solved, h)))
+                }).unwrap();
+                return Err(ValidationError::DuplicateCorner {
+                    corner: piece.clone(),
+                    missing: corner(&solved, missing).clone(),
+                });
+            }
+            seen_corners[home] = Some(slot);
+            cubies.corner_permutation[slot] = home as u8;
+            cubies.corner_orientation[slot] = piece.orientation(c[TOP], c[BOTTOM]).unwrap();
+        }
+        let mut seen_sides = [None; 12];
+        for (slot, &reference) in SIDE_REFERENCE.iter().enumerate() {
+            let piece = side(cube, slot);
+            let home = (0..12)
+                .find(|&home| same_side(piece, side(&solved, home)))
+                .ok_or_else(|| ValidationError::UnknownSide(piece.clone()))?;
+            if seen_sides[home].is_some() {
+                let missing = (0..12).find(|&h| {
+                    !(0..12).any(|s| same_side(side(cube, s), side(&solved, h)))
+                }).unwrap();
+                return Err(ValidationError::DuplicateSide {
+                    side: piece.clone(),
+                    missing: side(&solved, missing).clone(),
+                });
+            }
+            seen_sides[home] = Some(slot);
+            cubies.side_permutation[slot] = home as u8;
+            let orientation = piece.orientation(c[TOP], c[BOTTOM], c[FRONT], c[BACK]).unwrap();
+            cubies.side_orientation[slot] = orientation ^ reference as u8;
+        }
+        Ok(cubies)
+    }
+
+    /// Build the cube with these pieces, held with the given centers.
+    pub fn to_cube(&self, centers: [Color; 6]) -> Cube {
+        let solved = Cube::solved_with_centers(centers);
+        let mut cube = Cube::solved_with_centers(centers);
+        for slot in 0..8 {
+            let home = corner(&solved, self.corner_permutation[slot] as usize);
+            let o = self.corner_orientation[slot] as usize;
+            *corner_mut(&mut cube, slot) = Corner::new(home.color((3 - o) % 3),
+                                                       home.color((4 - o) % 3),
+                                                       home.color((5 - o) % 3));
+        }
+        for (slot, &slot_reference) in SIDE_REFERENCE.iter().enumerate() {
+            let home_slot = self.side_permutation[slot] as usize;
+            let home = side(&solved, home_slot);
+            let reference = home.color(SIDE_REFERENCE[home_slot]);
+            let other = home.color(1 - SIDE_REFERENCE[home_slot]);
+            let (reference, other) = if self.side_orientation[slot] == 0 {
+                (reference, other)
+            } else {
+                (other, reference)
+            };
+            *side_mut(&mut cube, slot) = if slot_reference == 0 {
+                Side::new(reference, other)
+            } else {
+                Side::new(other, reference)
+            };
+        }
+        cube
+    }
+}
+
+impl Cubies {
+    /// The cubies after performing `self` and then `other`.
+    pub fn multiply(&self, other: &Cubies) -> Cubies {
+        let mut result = Cubies::new();
+        for slot in 0..8 {
+            let from = other.corner_permutation[slot] as usize;
+            result.corner_permutation[slot] = self.corner_permutation[from];
+            result.corner_orientation[slot] =
+                (self.corner_orientation[from] + other.corner_orientation[slot]) % 3;
+        }
+        for slot in 0..12 {
+            let from = other.side_permutation[slot] as usize;
+            result.side_permutation[slot] = self.side_permutation[from];
+            result.side_orientation[slot] =
+                self.side_orientation[from] ^ other.side_orientation[slot];
+        }
+        result
+    }
+
+    /// Turn a face.  Only the turns in `FACE_TURNS` are supported since
+    /// the other movements carry the centers with them.  Returns false,
+    /// and stays put, for any other movement.
+    pub fn apply(&mut self, movement: &Movement) -> bool {
+        match FACE_TURNS.iter().position(|m| m == movement) {
+            Some(index) => {
+                *self = self.multiply(&face_turns()[index]);
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+/// What each of `FACE_TURNS` does to a solved cube.
+pub fn face_turns() -> &'static [Cubies] {
+    static FACE_TURN_CUBIES: OnceLock<Vec<Cubies>> = OnceLock::new();
+    FACE_TURN_CUBIES.get_or_init(|| {
+        FACE_TURNS.iter().map(|movement| {
+            let mut cube = Cube::new();
+            cube.apply(movement);
+            Cubies::from_cube(&cube).unwrap()
+        }).collect()
+    })
+}
+
+impl Default for Cubies {
+    fn default() -> Self {
+        Cubies::new()
+    }
+}
+
+/// Are the centers arranged as they are on a real cube held some way?
+pub fn valid_centers(centers: &[Color; 6]) -> bool {
+    let mut cube = Cube::new();
+    for i in 0..6 {
+        for _ in 0..4 {
+            if cube.centers == *centers {
+                return true;
+            }
+            cube.rotate_y_clockwise();
+        }
+        if i % 2 == 0 {
+            cube.rotate_x_clockwise();
+        } else {
+            cube.rotate_z_clockwise();
+        }
+    }
+    false
+}
+
+/// Is the permutation made of an odd number of swaps?
+pub fn is_odd(permutation: &[u8]) -> bool {
+    let mut inversions = 0;
+    for i in 0..permutation.len() {
+        for j in i + 1..permutation.len() {
+            if permutation[i] > permutation[j] {
+                inversions += 1;
+            }
+        }
+    }
+    inversions % 2 == 1
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parser::parse;
+
+    #[test]
+    fn test_solved() {
+        assert_eq!(Ok(Cubies::new()), Cubies::from_cube(&Cube::new()));
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("x y' z2").unwrap());
+        assert_eq!(Ok(Cubies::new()), Cubies::from_cube(&cube));
+    }
+
+    #[test]
+    fn test_valid_centers() {
+        let rotations = parse("x y z").unwrap();
+        let mut seen = Vec::new();
+        for a in &rotations {
+            for b in &rotations {
+                for c in &rotations {
+                    for times in 0..64 {
+                        let mut cube = Cube::new();
+                        for _ in 0..times % 4 { cube.apply(a); }
+                        for _ in 0..times / 4 % 4 { cube.apply(b); }
+                        for _ in 0..times / 16 { cube.apply(c); }
+                        assert!(valid_centers(&cube.centers));
+                        if !seen.contains(&cube.centers) {
+                            seen.push(cube.centers);
+                        }
+                    }
+                }
+            }
+        }
+        assert_eq!(24, seen.len());
+
+        let mut centers = Cube::new().centers;
+        centers.swap(0, 1);
+        assert!(!valid_centers(&centers));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("R U2 F' L D B' M E' S2 x r' u").unwrap());
+        let cubies = Cubies::from_cube(&cube).unwrap();
+        assert_eq!(cube, cubies.to_cube(cube.centers));
+    }
+
+    #[test]
+    fn test_apply() {
+        let movements = parse("R U2 F' L D B' R2 D2 F U' L2 B").unwrap();
+        let mut cube = Cube::new();
+        let mut cubies = Cubies::new();
+        for movement in &movements {
+            cube.apply(movement);
+            assert!(cubies.apply(movement));
+            assert_eq!(Cubies::from_cube(&cube).unwrap(), cubies);
+        }
+        for movement in &parse("M x r").unwrap() {
+            assert!(!cubies.apply(movement));
+        }
+        assert_eq!(Cubies::from_cube(&cube).unwrap(), cubies);
+    }
+
+    #[test]
+    fn test_orientation() {
+        // Turning the right face twists corners but flips no sides.
+        let mut cube = Cube::new();
+        cube.rotate_right_clockwise();
+        let cubies = Cubies::from_cube(&cube).unwrap();
+        assert_eq!([0, 0, 1, 2, 0, 1, 2, 0], cubies.corner_orientation);
+        assert_eq!([0; 12], cubies.side_orientation);
+
+        // Turning the front face flips four sides.
+        let mut cube = Cube::new();
+        cube.rotate_front_clockwise();
+        let cubies = Cubies::from_cube(&cube).unwrap();
+        assert_eq!([1, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0, 0], cubies.side_orientation);
+    }
+}
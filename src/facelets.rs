@@ -0,0 +1,226 @@
+use std::error;
+use std::fmt;
+use color::Color;
+use corner::Corner;
+use cube::{Cube, FRONT, BACK, LEFT, RIGHT, TOP, BOTTOM};
+use cubies;
+use side::Side;
+use validation::ValidationError;
+
+/// Where a sticker is on the pieces of a cube.
+#[derive(Clone, Copy)]
+enum Sticker {
+    /// The center on a face, indexed as in `Cube::centers`.
+    Center(usize),
+    /// A color of the corner in a slot.  Slots are numbered as in
+    /// `Cubies`.
+    Corner(usize, usize),
+    /// A color of the side in a slot.
+    Side(usize, usize),
+}
+
+/// The stickers in the standard order: the top, right, front, bottom,
+/// left and back faces, each read row by row as it is drawn in
+/// `Cube`'s `Display` impl.  The back is read as if looking at it from
+/// behind.
+const STICKERS: [Sticker; 54] = [
+    Sticker::Corner(1, 0), Sticker::Side(2, 0), Sticker::Corner(2, 0),
+    Sticker::Side(1, 0), Sticker::Center(TOP), Sticker::Side(3, 0),
+    Sticker::Corner(0, 0), Sticker::Side(0, 0), Sticker::Corner(3, 0),
+
+    Sticker::Corner(3, 1), Sticker::Side(3, 1), Sticker::Corner(2, 2),
+    Sticker::Side(7, 0), Sticker::Center(RIGHT), Sticker::Side(6, 1),
+    Sticker::Corner(5, 2), Sticker::Side(9, 1), Sticker::Corner(6, 1),
+
+    Sticker::Corner(0, 1), Sticker::Side(0, 1), Sticker::Corner(3, 2),
+    Sticker::Side(4, 0), Sticker::Center(FRONT), Sticker::Side(7, 1),
+    Sticker::Corner(4, 2), Sticker::Side(8, 1), Sticker::Corner(5, 1),
+
+    Sticker::Corner(4, 0), Sticker::Side(8, 0), Sticker::Corner(5, 0),
+    Sticker::Side(11, 0), Sticker::Center(BOTTOM), Sticker::Side(9, 0),
+    Sticker::Corner(7, 0), Sticker::Side(10, 0), Sticker::Corner(6, 0),
+
+    Sticker::Corner(1, 1), Sticker::Side(1, 1), Sticker::Corner(0, 2),
+    Sticker::Side(5, 0), Sticker::Center(LEFT), Sticker::Side(4, 1),
+    Sticker::Corner(7, 2), Sticker::Side(11, 1), Sticker::Corner(4, 1),
+
+    Sticker::Corner(2, 1), Sticker::Side(2, 1), Sticker::Corner(1, 2),
+    Sticker::Side(6, 0), Sticker::Center(BACK), Sticker::Side(5, 1),
+    Sticker::Corner(6, 2), Sticker::Side(10, 1), Sticker::Corner(7, 1),
+];
+
+/// The letter for each face, indexed as in `Cube::centers`.
+const LETTERS: [char; 6] = ['F', 'B', 'L', 'R', 'U', 'D'];
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FaceletError {
+    /// There must be exactly 54 facelets.
+    WrongLength(usize),
+    /// A facelet is not one of `URFDLB`.  The index starts at 0.
+    InvalidFacelet { index: usize, facelet: char },
+    /// A center facelet names a different face than the one it is on.
+    WrongCenter { index: usize, facelet: char },
+    /// A face letter does not appear exactly nine times.
+    WrongCount { facelet: char, count: usize },
+    /// The facelets describe pieces that cannot be solved.
+    Invalid(ValidationError),
+}
+
+impl fmt::Display for FaceletError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FaceletError::WrongLength(length) =>
+                write!(fmt, "expected 54 facelets but found {}", length),
+            FaceletError::InvalidFacelet { index, facelet } =>
+                write!(fmt, "invalid facelet `{}` at index {}", facelet, index),
+            FaceletError::WrongCenter { index, facelet } =>
+                write!(fmt, "the center at index {} is `{}`", index, facelet),
+            FaceletError::WrongCount { facelet, count } =>
+                write!(fmt, "`{}` appears {} times instead of 9", facelet, count),
+            FaceletError::Invalid(ref error) => write!(fmt, "{}", error),
+        }
+    }
+}
+
+impl error::Error for FaceletError {}
+
+impl From<ValidationError> for FaceletError {
+    fn from(error: ValidationError) -> Self {
+        FaceletError::Invalid(error)
+    }
+}
+
+fn sticker(cube: &Cube, sticker: Sticker) -> Color {
+    match sticker {
+        Sticker::Center(face) => cube.centers[face],
+        Sticker::Corner(slot, i) => cubies::corner(cube, slot).color(i),
+        Sticker::Side(slot, i) => cubies::side(cube, slot).color(i),
+    }
+}
+
+impl Cube {
+    /// Describe the cube as 54 face letters in the order used by
+    /// Kociemba's solver: `U1..U9 R1..R9 F1..F9 D1..D9 L1..L9 B1..B9`.
+    /// Each sticker is named by the face whose center has its color, so
+    /// this fails if the centers are not those of a real cube.
+    pub fn to_facelets(&self) -> Result<String, FaceletError> {
+        if !cubies::valid_centers(&self.centers) {
+            return Err(FaceletError::Invalid(ValidationError::InvalidCenters));
+        }
+        let mut faces = [' '; 6];
+        for (face, &color) in self.centers.iter().enumerate() {
+            faces[color as usize] = LETTERS[face];
+        }
+        Ok(STICKERS.iter().map(|&s| faces[sticker(self, s) as usize]).collect())
+    }
+
+    /// Read a cube described by `to_facelets`.  The cube is held as
+    /// `Cube::new` holds it, so `U` is red and `F` is white.
+    pub fn from_facelets(facelets: &str) -> Result<Cube, FaceletError> {
+        let facelets: Vec<char> = facelets.chars().collect();
+        if facelets.len() != 54 {
+            return Err(FaceletError::WrongLength(facelets.len()));
+        }
+        let centers = Cube::new().centers;
+        let mut colors = Vec::with_capacity(54);
+        for (index, &facelet) in facelets.iter().enumerate() {
+            let face = match LETTERS.iter().position(|&l| l == facelet) {
+                Some(face) => face,
+                None => return Err(FaceletError::InvalidFacelet { index, facelet }),
+            };
+            if let Sticker::Center(center) = STICKERS[index] {
+                if center != face {
+                    return Err(FaceletError::WrongCenter { index, facelet });
+                }
+            }
+            colors.push(centers[face]);
+        }
+        for facelet in "URFDLB".chars() {
+            let count = facelets.iter().filter(|&&f| f == facelet).count();
+            if count != 9 {
+                return Err(FaceletError::WrongCount { facelet, count });
+            }
+        }
+
+        let mut corners = [[Color::White; 3]; 8];
+        let mut sides = [[Color::White; 2]; 12];
+        for (&s, &color) in STICKERS.iter().zip(colors.iter()) {
+            match s {
+                Sticker::Center(_) => {},
+                Sticker::Corner(slot, i) => corners[slot][i] = color,
+                Sticker::Side(slot, i) => sides[slot][i] = color,
+            }
+        }
+        let corner = |slot: usize| {
+            Corner::new(corners[slot][0], corners[slot][1], corners[slot][2])
+        };
+        let side = |slot: usize| Side::new(sides[slot][0], sides[slot][1]);
+        let cube = Cube {
+            centers,
+            bottom_sides: [side(8), side(9), side(10), side(11)],
+            bottom_corners: [corner(4), corner(5), corner(6), corner(7)],
+            middle_sides: [side(4), side(5), side(6), side(7)],
+            top_sides: [side(0), side(1), side(2), side(3)],
+            top_corners: [corner(0), corner(1), corner(2), corner(3)],
+        };
+        cube.validate()?;
+        Ok(cube)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parser::parse;
+
+    const SOLVED: &str = "UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB";
+
+    #[test]
+    fn test_to_facelets() {
+        let mut cube = Cube::new();
+        assert_eq!(Ok(SOLVED.to_string()), cube.to_facelets());
+
+        cube.rotate_right_clockwise();
+        assert_eq!("UUFUUFUUFRRRRRRRRRFFDFFDFFDDDBDDBDDBLLLLLLLLLUBBUBBUBB",
+                   cube.to_facelets().unwrap());
+
+        let mut cube = Cube::new();
+        cube.rotate_top_clockwise();
+        assert_eq!("UUUUUUUUUBBBRRRRRRRRRFFFFFFDDDDDDDDDFFFLLLLLLLLLBBBBBB",
+                   cube.to_facelets().unwrap());
+
+        // The faces are named by their centers.
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("x y' M E S").unwrap());
+        cube.apply_all(&parse("S' E' M'").unwrap());
+        assert_eq!(Ok(SOLVED.to_string()), cube.to_facelets());
+
+        let mut cube = Cube::new();
+        cube.centers[FRONT] = cube.centers[BACK];
+        assert_eq!(Err(FaceletError::Invalid(ValidationError::InvalidCenters)),
+                   cube.to_facelets());
+    }
+
+    #[test]
+    fn test_from_facelets() {
+        assert_eq!(Ok(Cube::new()), Cube::from_facelets(SOLVED));
+
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("R U2 F' L D B' R2 D2").unwrap());
+        assert_eq!(Ok(cube.clone()), Cube::from_facelets(&cube.to_facelets().unwrap()));
+    }
+
+    #[test]
+    fn test_from_facelets_errors() {
+        assert_eq!(Err(FaceletError::WrongLength(53)), Cube::from_facelets(&SOLVED[1..]));
+        assert_eq!(Err(FaceletError::InvalidFacelet { index: 9, facelet: 'X' }),
+                   Cube::from_facelets(&SOLVED.replacen("R", "X", 1)));
+        assert_eq!(Err(FaceletError::WrongCenter { index: 4, facelet: 'R' }),
+                   Cube::from_facelets("UUUURUUUURRRRURRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB"));
+        assert_eq!(Err(FaceletError::WrongCount { facelet: 'U', count: 10 }),
+                   Cube::from_facelets(&SOLVED.replacen("R", "U", 1)));
+        // The top front right corner twisted in place.
+        assert_eq!(Err(FaceletError::Invalid(ValidationError::TwistedCorner)),
+                   Cube::from_facelets("UUUUUUUUFURRRRRRRRFFRFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB"));
+    }
+}
@@ -0,0 +1,152 @@
+//! A log of the movements made to a cube so they can be undone and
+//! redone.  Movements are logged in steps, one for each sequence that
+//! was applied at once, and undo and redo work a step at a time.
+
+use cube::Cube;
+use movement::Movement;
+
+#[derive(Debug, Clone)]
+pub struct History {
+    start: Cube,
+    cube: Cube,
+    steps: Vec<Vec<Movement>>,
+    /// How many of `steps` are applied to `cube`.  The rest were undone
+    /// and can be redone.
+    position: usize,
+}
+
+impl History {
+    /// An empty log starting from `start`.
+    pub fn new(start: Cube) -> Self {
+        History { cube: start.clone(), start, steps: Vec::new(), position: 0 }
+    }
+
+    /// The cube with every step up to the current position applied.
+    pub fn cube(&self) -> &Cube {
+        &self.cube
+    }
+
+    /// Every step logged, including those that were undone.
+    pub fn steps(&self) -> &[Vec<Movement>] {
+        &self.steps
+    }
+
+    /// How many steps are applied.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Forget every step and start again from `start`.
+    pub fn reset(&mut self, start: Cube) {
+        *self = History::new(start);
+    }
+
+    /// Apply `movements` as a new step.  The steps that were undone can
+    /// no longer be redone.  Applying nothing logs nothing.
+    pub fn apply(&mut self, movements: &[Movement]) {
+        if movements.is_empty() {
+            return;
+        }
+        self.steps.truncate(self.position);
+        self.steps.push(movements.to_vec());
+        self.position += 1;
+        self.cube.apply_all(movements);
+    }
+
+    /// Undo the last step applied and return it.
+    pub fn undo(&mut self) -> Option<&[Movement]> {
+        if self.position == 0 {
+            return None;
+        }
+        self.position -= 1;
+        let step = &self.steps[self.position];
+        for movement in step.iter().rev() {
+            self.cube.apply(&movement.inverse());
+        }
+        Some(step)
+    }
+
+    /// Redo the last step undone and return it.
+    pub fn redo(&mut self) -> Option<&[Movement]> {
+        if self.position == self.steps.len() {
+            return None;
+        }
+        let step = &self.steps[self.position];
+        self.position += 1;
+        self.cube.apply_all(step);
+        Some(step)
+    }
+
+    /// Go to the point after the first `position` steps, undoing or
+    /// redoing as many as that takes.  Returns false, and stays put, if
+    /// there are not that many steps.
+    pub fn go_to(&mut self, position: usize) -> bool {
+        if position > self.steps.len() {
+            return false;
+        }
+        self.position = position;
+        self.cube = self.start.clone();
+        for step in &self.steps[..position] {
+            self.cube.apply_all(step);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parser::parse;
+
+    fn cube_after(alg: &str) -> Cube {
+        let mut cube = Cube::new();
+        cube.apply_all(&parse(alg).unwrap());
+        cube
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = History::new(Cube::new());
+        assert_eq!(None, history.undo());
+        history.apply(&parse("R U").unwrap());
+        history.apply(&parse("F'").unwrap());
+        history.apply(&[]);
+        assert_eq!(2, history.steps().len());
+        assert_eq!(&cube_after("R U F'"), history.cube());
+
+        assert_eq!(Some(&parse("F'").unwrap()[..]), history.undo());
+        assert_eq!(&cube_after("R U"), history.cube());
+        assert_eq!(Some(&parse("R U").unwrap()[..]), history.undo());
+        assert_eq!(None, history.undo());
+        assert!(history.cube().is_solved());
+
+        assert_eq!(Some(&parse("R U").unwrap()[..]), history.redo());
+        assert_eq!(1, history.position());
+        history.apply(&parse("D2").unwrap());
+        assert_eq!(None, history.redo());
+        assert_eq!(2, history.steps().len());
+        assert_eq!(&cube_after("R U D2"), history.cube());
+    }
+
+    #[test]
+    fn test_go_to() {
+        let mut start = Cube::new();
+        start.apply_all(&parse("L B").unwrap());
+        let mut history = History::new(start);
+        for alg in &["R", "U", "F2"] {
+            history.apply(&parse(alg).unwrap());
+        }
+        assert!(history.go_to(1));
+        assert_eq!(&cube_after("L B R"), history.cube());
+        assert!(history.go_to(3));
+        assert_eq!(&cube_after("L B R U F2"), history.cube());
+        assert!(!history.go_to(4));
+        assert_eq!(3, history.position());
+        assert!(history.go_to(0));
+        assert_eq!(&cube_after("L B"), history.cube());
+
+        history.reset(Cube::new());
+        assert!(history.steps().is_empty());
+        assert!(history.cube().is_solved());
+    }
+}
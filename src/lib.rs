@@ -0,0 +1,18 @@
+extern crate rand;
+
+pub mod color;
+pub mod cube;
+pub mod corner;
+pub mod side;
+pub mod movement;
+pub mod parser;
+pub mod algorithm;
+pub mod simplify;
+pub mod cubies;
+pub mod validation;
+pub mod facelets;
+pub mod coordinates;
+pub mod predicates;
+pub mod solver;
+pub mod scramble;
+pub mod history;
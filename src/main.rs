@@ -1,119 +1,386 @@
 extern crate rand;
+extern crate rubick;
 
-mod color;
-mod cube;
-mod corner;
-mod side;
-mod predicates;
-use cube::Cube;
-use std::io;
+use rand::{SeedableRng, StdRng};
+use rubick::cube::Cube;
+use rubick::history::History;
+use rubick::movement::{format_movements, Movement};
+use rubick::parser;
+use rubick::scramble;
+use rubick::solver::{Beginner, Solver};
+use rubick::solver::benchmark;
+use rubick::solver::cache::{self, TableCache};
+use rubick::solver::cfop::Cfop;
+use rubick::solver::kociemba::{self, Kociemba};
+use rubick::solver::optimal::{Optimal, Pattern, PatternDatabase};
+use rubick::solver::roux::{self, Roux};
+use rubick::solver::thistlethwaite::{self, Thistlethwaite};
+use std::env;
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::process;
 
-fn main() {
-    let mut cube = Cube::new();
+const USAGE: &str = "\
+usage: rubick [--seed SEED] [--color auto|always|never] [COMMAND]
+
+Without a command, read movements from stdin and show the cube.
+
+commands:
+    apply [--facelets CUBE] [--format net|facelets] ALG
+        print the cube after ALG
+    solve [--solver NAME] (--facelets CUBE | ALG)
+        print movements that solve CUBE, or the cube ALG makes
+    scramble [-n COUNT]
+        print COUNT random-state scrambles, one per line
+    verify [--facelets CUBE] ALG
+        print whether ALG solves CUBE and exit with 1 if it does not
+    bench [--optimal] [COUNT [SEED]]
+        compare the solvers over COUNT scrambles, leaving out optimal
+        unless --optimal is given, as its tables take over a minute
+        to build
+    gen-tables
+        build every table and save it in the table directory
+
+CUBE is 54 letters from URFDLB in the order Kociemba's solver uses, and
+starts solved without --facelets.  NAME is one of beginner, cfop, roux,
+thistlethwaite, kociemba (the default) or optimal.  Bad input exits
+with 2, and output cut off by a closed pipe with 141.  Cubes are drawn
+in color when stdout is a terminal and NO_COLOR is not set, unless
+--color says otherwise.
+
+Tables are saved in and loaded from the table directory, which is
+$RUBICK_TABLES, or else $XDG_CACHE_HOME/rubick, or else ~/.cache/rubick.
+Without any of RUBICK_TABLES, XDG_CACHE_HOME or HOME set, tables are
+built each time and not saved.
+";
+
+/// The solvers `bench` compares.  Optimal is left out unless asked for,
+/// since building its tables takes over a minute.
+const BENCH_SOLVERS: [&str; 5] = ["beginner", "cfop", "roux", "thistlethwaite", "kociemba"];
+
+fn usage() -> ! {
+    eprint!("{}", USAGE);
+    process::exit(2);
+}
+
+/// Report bad input and exit with 2.
+fn invalid<E: fmt::Display>(what: &str, error: E) -> ! {
+    eprintln!("rubick: invalid {}: {}", what, error);
+    process::exit(2);
+}
+
+/// Remove `name` and the value after it from `args`.
+fn take_option(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let i = args.iter().position(|a| a == name)?;
+    if i + 1 == args.len() {
+        usage();
+    }
+    let value = args.remove(i + 1);
+    args.remove(i);
+    Some(value)
+}
+
+/// Remove the flag `name` from `args` and return whether it was there.
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|a| a == name) {
+        Some(i) => {
+            args.remove(i);
+            true
+        },
+        None => false,
+    }
+}
+
+fn read_cube(facelets: Option<String>) -> Cube {
+    match facelets {
+        Some(facelets) => Cube::from_facelets(&facelets)
+            .unwrap_or_else(|e| invalid("facelets", e)),
+        None => Cube::new(),
+    }
+}
+
+fn read_movements(alg: &str) -> Vec<Movement> {
+    parser::parse(alg).unwrap_or_else(|e| invalid("algorithm", e))
+}
+
+/// The solver called `name`.
+fn solver(name: &str) -> Option<Box<dyn Solver>> {
+    let solver: Box<dyn Solver> = match name {
+        "beginner" => Box::new(Beginner::new()),
+        "cfop" => Box::new(Cfop::new()),
+        "roux" => Box::new(Roux::new()),
+        "thistlethwaite" => Box::new(Thistlethwaite::new()),
+        "kociemba" => Box::new(Kociemba::default()),
+        "optimal" => Box::new(Optimal::new(&Pattern::korf())),
+        _ => return None,
+    };
+    Some(solver)
+}
+
+/// Whether to draw cubes in color for `--color when`.
+fn use_color(when: &str) -> bool {
+    match when {
+        "always" => true,
+        "never" => false,
+        "auto" => io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none(),
+        _ => invalid("color", when),
+    }
+}
+
+/// The net of `cube`, in color or not.
+fn net(cube: &Cube, color: bool) -> String {
+    if color {
+        cube.colored().to_string()
+    } else {
+        cube.to_string()
+    }
+}
+
+/// Write `line` to the locked stdout of a subcommand.  Scripts may stop
+/// reading early, as `head` does, so a closed pipe exits quietly with
+/// the status a shell gives a program killed by `SIGPIPE`.
+fn write_line<T: fmt::Display>(out: &mut io::StdoutLock, line: T) {
+    if let Err(e) = writeln!(out, "{}", line) {
+        if e.kind() == io::ErrorKind::BrokenPipe {
+            process::exit(141);
+        }
+        eprintln!("rubick: {}", e);
+        process::exit(1);
+    }
+}
+
+/// A generator seeded with `seed`, or with a random seed without one.
+fn rng(seed: Option<usize>) -> StdRng {
+    match seed {
+        Some(seed) => SeedableRng::from_seed(&[seed][..]),
+        None => StdRng::new().unwrap(),
+    }
+}
+
+/// Print the steps in `history`, marking where it is and which steps
+/// were undone.
+fn print_history(history: &History) {
+    println!("  0  start{}", if history.position() == 0 { "  <" } else { "" });
+    for (i, step) in history.steps().iter().enumerate() {
+        let mark = if i + 1 == history.position() {
+            "  <"
+        } else if i >= history.position() {
+            "  (undone)"
+        } else {
+            ""
+        };
+        println!("{:>3}  {}{}", i + 1, format_movements(step), mark);
+    }
+}
+
+/// Shuffles use `seed`, or a random seed without one, and print the
+/// turns they make so they can be repeated.  Movements are logged until
+/// the cube is reset or shuffled: `u!` undoes the last line, `r!` redoes
+/// it, `history` lists the log and `goto N` goes to the point after its
+/// first `N` lines.  `color on` and `color off` choose how the cube is
+/// drawn.
+fn repl(seed: Option<usize>, mut color: bool) {
+    let mut rng = rng(seed);
+    let mut history = History::new(Cube::new());
     let mut input = String::new();
     loop {
         input.clear();
-        println!("{}", cube);
-        io::stdin().read_line(&mut input).unwrap();
-        let mut iter = input.chars();
-        let mut c = match iter.next() {
-            Some(c) => c,
-            None => break,
-        };
-        loop {
-            c = c.to_uppercase().next().unwrap();
-            if c.is_whitespace() {
-            } else if c == 'R' {
-                match iter.next() {
-                    Some('\'') => cube.rotate_right_counter_clockwise(),
-                    Some(ch) => {
-                        cube.rotate_right_clockwise();
-                        c = ch;
-                        continue;
-                    },
-                    None => {
-                        cube.rotate_right_clockwise();
-                        break;
-                    },
-                }
-            } else if c == 'U' {
-                match iter.next() {
-                    Some('\'') => cube.rotate_top_counter_clockwise(),
-                    Some(ch) => {
-                        cube.rotate_top_clockwise();
-                        c = ch;
-                        continue;
-                    },
-                    None => {
-                        cube.rotate_top_clockwise();
-                        break;
-                    },
-                }
-            } else if c == 'F' {
-                match iter.next() {
-                    Some('\'') => cube.rotate_front_counter_clockwise(),
-                    Some(ch) => {
-                        cube.rotate_front_clockwise();
-                        c = ch;
-                        continue;
-                    },
-                    None => {
-                        cube.rotate_front_clockwise();
-                        break;
-                    },
-                }
-            } else if c == 'L' {
-                match iter.next() {
-                    Some('\'') => cube.rotate_left_counter_clockwise(),
-                    Some(ch) => {
-                        cube.rotate_left_clockwise();
-                        c = ch;
-                        continue;
-                    },
-                    None => {
-                        cube.rotate_left_clockwise();
-                        break;
-                    },
-                }
-            } else if c == 'D' {
-                match iter.next() {
-                    Some('\'') => cube.rotate_bottom_counter_clockwise(),
-                    Some(ch) => {
-                        cube.rotate_bottom_clockwise();
-                        c = ch;
-                        continue;
-                    },
-                    None => {
-                        cube.rotate_bottom_clockwise();
-                        break;
-                    },
-                }
-            } else if c == 'B' {
-                match iter.next() {
-                    Some('\'') => cube.rotate_back_counter_clockwise(),
-                    Some(ch) => {
-                        cube.rotate_back_clockwise();
-                        c = ch;
-                        continue;
-                    },
-                    None => {
-                        cube.rotate_back_clockwise();
-                        break;
-                    },
+        println!("{}", net(history.cube(), color));
+        if io::stdin().read_line(&mut input).unwrap() == 0 {
+            break;
+        }
+        let line = input.trim();
+        match line {
+            "!" => return,
+            "#" => history.reset(Cube::new()),
+            "*" => {
+                let mut cube = history.cube().clone();
+                println!("{}", format_movements(&cube.shuffle_with(&mut rng)));
+                history.reset(cube);
+            },
+            "u!" => if history.undo().is_none() {
+                println!("Nothing to undo");
+            },
+            "r!" => if history.redo().is_none() {
+                println!("Nothing to redo");
+            },
+            "history" => print_history(&history),
+            "color on" => color = true,
+            "color off" => color = false,
+            _ if line.starts_with("goto ") => {
+                match line["goto ".len()..].trim().parse() {
+                    Ok(position) if history.go_to(position) => (),
+                    _ => println!("goto takes a number from 0 to {}", history.steps().len()),
                 }
-            } else if c == '!' {
-                return;
-            } else if c == '#' {
-                cube = Cube::new();
-            } else if c == '*' {
-                cube.shuffle();
-            } else {
-                panic!("Invalid character, {}", c);
+            },
+            _ => match parser::parse(line) {
+                Ok(movements) => {
+                    history.apply(&movements);
+                    if !movements.is_empty() && history.cube().is_solved_in_any_orientation() {
+                        println!("Solved!");
+                    }
+                },
+                Err(e) => println!("{}", e),
+            },
+        }
+    }
+}
+
+/// Print the cube after `movements` as a net or as facelets.
+fn apply(mut cube: Cube, movements: &[Movement], format: &str, color: bool) {
+    let mut out = io::stdout().lock();
+    cube.apply_all(movements);
+    match format {
+        "net" => write_line(&mut out, net(&cube, color)),
+        "facelets" => match cube.to_facelets() {
+            Ok(facelets) => write_line(&mut out, facelets),
+            Err(e) => invalid("cube", e),
+        },
+        _ => invalid("format", format),
+    }
+}
+
+/// Print movements that solve `cube` on one line, or exit with 1 if
+/// `solver` cannot find any.
+fn solve(cube: &Cube, solver: &dyn Solver) {
+    match solver.solve(cube) {
+        Ok(movements) => write_line(&mut io::stdout().lock(), format_movements(&movements)),
+        Err(e) => {
+            eprintln!("rubick: {}", e);
+            process::exit(1);
+        },
+    }
+}
+
+/// Print `count` scrambles made by `scramble::random_state`, or exit
+/// with 1 if one cannot be made.
+fn scramble(count: usize, seed: Option<usize>) {
+    let mut out = io::stdout().lock();
+    let mut rng = rng(seed);
+    for _ in 0..count {
+        match scramble::random_state(&mut rng) {
+            Ok(movements) => write_line(&mut out, format_movements(&movements)),
+            Err(e) => {
+                eprintln!("rubick: {}", e);
+                process::exit(1);
+            },
+        }
+    }
+}
+
+/// Print whether `movements` solve `cube`, in any orientation, and exit
+/// with 1 if they do not.
+fn verify(mut cube: Cube, movements: &[Movement]) {
+    let mut out = io::stdout().lock();
+    cube.apply_all(movements);
+    if cube.is_solved_in_any_orientation() {
+        write_line(&mut out, "solved");
+    } else {
+        write_line(&mut out, "unsolved");
+        process::exit(1);
+    }
+}
+
+/// Compare the solvers over `count` scrambles made from `seed`, and
+/// optimal too if `optimal` is set.
+fn bench(count: usize, seed: usize, optimal: bool) {
+    let scrambles = benchmark::scrambles(count, seed);
+    println!("{}", benchmark::header());
+    let extra = if optimal { Some(&"optimal") } else { None };
+    for name in BENCH_SOLVERS.iter().chain(extra) {
+        let solver = solver(name).unwrap();
+        println!("{}", benchmark::benchmark(name, &*solver, &scrambles));
+    }
+}
+
+/// Build every table and save it, replacing any saved before.
+fn gen_tables() {
+    let cache = TableCache::standard().rebuilding();
+    let dir = match cache.dir() {
+        Some(dir) => dir.to_path_buf(),
+        None => {
+            eprintln!("rubick: no table directory, see the end of rubick --help");
+            process::exit(1);
+        },
+    };
+    cache::install(cache).unwrap();
+    println!("saving tables in {}", dir.display());
+    println!("building the Thistlethwaite tables");
+    thistlethwaite::Tables::get();
+    println!("building the Kociemba tables");
+    kociemba::Tables::get();
+    println!("building the Roux tables");
+    roux::Tables::get();
+    for pattern in Pattern::korf() {
+        println!("building {}", pattern.name());
+        PatternDatabase::new(pattern);
+    }
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{}", USAGE);
+        return;
+    }
+    let seed = take_option(&mut args, "--seed")
+        .map(|seed| seed.parse().unwrap_or_else(|e| invalid("seed", e)));
+    let color = take_option(&mut args, "--color").unwrap_or_else(|| "auto".to_string());
+    let color = use_color(&color);
+    let command = if args.is_empty() { None } else { Some(args.remove(0)) };
+    let number = |arg: &String| arg.parse().unwrap_or_else(|e| invalid("number", e));
+    match command.as_deref() {
+        Some("gen-tables") if args.is_empty() => return gen_tables(),
+        _ => cache::install(TableCache::standard()).unwrap(),
+    }
+    match command.as_deref() {
+        None => repl(seed, color),
+        Some("apply") => {
+            let cube = read_cube(take_option(&mut args, "--facelets"));
+            let format = take_option(&mut args, "--format").unwrap_or_else(|| "net".to_string());
+            match args.as_slice() {
+                [alg] => apply(cube, &read_movements(alg), &format, color),
+                _ => usage(),
             }
-            c = match iter.next() {
-                Some(c) => c,
-                None => break,
+        },
+        Some("solve") => {
+            let facelets = take_option(&mut args, "--facelets");
+            let name = take_option(&mut args, "--solver").unwrap_or_else(|| "kociemba".to_string());
+            let solver = solver(&name).unwrap_or_else(|| invalid("solver", name));
+            let cube = match (facelets, args.as_slice()) {
+                (Some(facelets), []) => read_cube(Some(facelets)),
+                (None, [alg]) => {
+                    let mut cube = Cube::new();
+                    cube.apply_all(&read_movements(alg));
+                    cube
+                },
+                _ => usage(),
             };
-        }
+            solve(&cube, &*solver);
+        },
+        Some("scramble") => {
+            let count = take_option(&mut args, "-n").map_or(1, |n| number(&n));
+            match args.as_slice() {
+                [] => scramble(count, seed),
+                _ => usage(),
+            }
+        },
+        Some("verify") => {
+            let cube = read_cube(take_option(&mut args, "--facelets"));
+            match args.as_slice() {
+                [alg] => verify(cube, &read_movements(alg)),
+                _ => usage(),
+            }
+        },
+        Some("bench") => {
+            let optimal = take_flag(&mut args, "--optimal");
+            if args.len() > 2 {
+                usage();
+            }
+            let count = args.first().map_or(100, &number);
+            let seed = args.get(1).map_or(seed.unwrap_or(0), &number);
+            bench(count, seed, optimal);
+        },
+        _ => usage(),
     }
 }
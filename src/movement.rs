@@ -1,4 +1,7 @@
-#[derive(Debug, PartialEq, Eq, Hash)]
+use std::fmt;
+use rand::Rng;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Direction {
     Front,
     Back,
@@ -6,18 +9,267 @@ pub enum Direction {
     Right,
     Top,
     Bottom,
+    /// The slice between the left and right faces.  It turns the
+    /// same way as the left face.
     Middle,
+    /// The slice between the top and bottom faces.  It turns the same
+    /// way as the bottom face.
+    Equator,
+    /// The slice between the front and back faces.  It turns the same
+    /// way as the front face.
+    Standing,
+    /// The front face and the standing slice.
+    WideFront,
+    /// The back face and the standing slice.
+    WideBack,
+    /// The left face and the middle slice.
+    WideLeft,
+    /// The right face and the middle slice.
+    WideRight,
+    /// The top face and the equator slice.
+    WideTop,
+    /// The bottom face and the equator slice.
+    WideBottom,
+    /// Rotate the whole cube the same way as the right face.
+    X,
+    /// Rotate the whole cube the same way as the top face.
+    Y,
+    /// Rotate the whole cube the same way as the front face.
+    Z,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Axis {
+    LeftRight,
+    TopBottom,
+    FrontBack,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Times {
     Clockwise,
     CounterClockwise,
     Double,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct Movement {
     pub direction: Direction,
     pub times: Times,
 }
+
+impl Direction {
+    /// The axis the direction turns around.  Directions on the same
+    /// axis commute with each other.
+    pub fn axis(self) -> Axis {
+        match self {
+            Direction::Left | Direction::Right | Direction::Middle |
+            Direction::WideLeft | Direction::WideRight | Direction::X => Axis::LeftRight,
+            Direction::Top | Direction::Bottom | Direction::Equator |
+            Direction::WideTop | Direction::WideBottom | Direction::Y => Axis::TopBottom,
+            Direction::Front | Direction::Back | Direction::Standing |
+            Direction::WideFront | Direction::WideBack | Direction::Z => Axis::FrontBack,
+        }
+    }
+}
+
+impl Times {
+    /// The number of clockwise quarter turns.
+    pub fn quarter_turns(self) -> u8 {
+        match self {
+            Times::Clockwise => 1,
+            Times::Double => 2,
+            Times::CounterClockwise => 3,
+        }
+    }
+
+    /// The turn made of `quarter_turns` clockwise quarter turns, or
+    /// `None` if they cancel out.
+    pub fn from_quarter_turns(quarter_turns: u8) -> Option<Times> {
+        match quarter_turns % 4 {
+            1 => Some(Times::Clockwise),
+            2 => Some(Times::Double),
+            3 => Some(Times::CounterClockwise),
+            _ => None,
+        }
+    }
+
+    /// Turn the other way.
+    pub fn inverse(self) -> Times {
+        match self {
+            Times::Clockwise => Times::CounterClockwise,
+            Times::CounterClockwise => Times::Clockwise,
+            Times::Double => Times::Double,
+        }
+    }
+}
+
+impl Movement {
+    pub fn new(direction: Direction, times: Times) -> Self {
+        Movement { direction, times }
+    }
+
+    /// The movement that undoes this one.
+    pub fn inverse(&self) -> Movement {
+        Movement::new(self.direction, self.times.inverse())
+    }
+
+    /// Reflect the movement through the plane between the left and
+    /// right faces.
+    pub fn mirror_left_right(&self) -> Movement {
+        let direction = match self.direction {
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::WideLeft => Direction::WideRight,
+            Direction::WideRight => Direction::WideLeft,
+            direction => direction,
+        };
+        match direction {
+            // Turns around the left right axis keep their direction.
+            Direction::Middle | Direction::X => Movement::new(direction, self.times),
+            _ => Movement::new(direction, self.times.inverse()),
+        }
+    }
+
+    /// Reflect the movement through the plane between the front and
+    /// back faces.
+    pub fn mirror_front_back(&self) -> Movement {
+        let direction = match self.direction {
+            Direction::Front => Direction::Back,
+            Direction::Back => Direction::Front,
+            Direction::WideFront => Direction::WideBack,
+            Direction::WideBack => Direction::WideFront,
+            direction => direction,
+        };
+        match direction {
+            // Turns around the front back axis keep their direction.
+            Direction::Standing | Direction::Z => Movement::new(direction, self.times),
+            _ => Movement::new(direction, self.times.inverse()),
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}",
+               match *self {
+                   Direction::Front => "F",
+                   Direction::Back => "B",
+                   Direction::Left => "L",
+                   Direction::Right => "R",
+                   Direction::Top => "U",
+                   Direction::Bottom => "D",
+                   Direction::Middle => "M",
+                   Direction::Equator => "E",
+                   Direction::Standing => "S",
+                   Direction::WideFront => "f",
+                   Direction::WideBack => "b",
+                   Direction::WideLeft => "l",
+                   Direction::WideRight => "r",
+                   Direction::WideTop => "u",
+                   Direction::WideBottom => "d",
+                   Direction::X => "x",
+                   Direction::Y => "y",
+                   Direction::Z => "z",
+               })
+    }
+}
+
+impl fmt::Display for Times {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}",
+               match *self {
+                   Times::Clockwise => "",
+                   Times::CounterClockwise => "'",
+                   Times::Double => "2",
+               })
+    }
+}
+
+impl fmt::Display for Movement {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}{}", self.direction, self.times)
+    }
+}
+
+/// The eighteen turns of the outer faces, ordered by face (top,
+/// right, front, bottom, left, back) and then clockwise, double and
+/// counter clockwise.  Solvers number movements by their index here.
+pub const FACE_TURNS: [Movement; 18] = [
+    Movement { direction: Direction::Top, times: Times::Clockwise },
+    Movement { direction: Direction::Top, times: Times::Double },
+    Movement { direction: Direction::Top, times: Times::CounterClockwise },
+    Movement { direction: Direction::Right, times: Times::Clockwise },
+    Movement { direction: Direction::Right, times: Times::Double },
+    Movement { direction: Direction::Right, times: Times::CounterClockwise },
+    Movement { direction: Direction::Front, times: Times::Clockwise },
+    Movement { direction: Direction::Front, times: Times::Double },
+    Movement { direction: Direction::Front, times: Times::CounterClockwise },
+    Movement { direction: Direction::Bottom, times: Times::Clockwise },
+    Movement { direction: Direction::Bottom, times: Times::Double },
+    Movement { direction: Direction::Bottom, times: Times::CounterClockwise },
+    Movement { direction: Direction::Left, times: Times::Clockwise },
+    Movement { direction: Direction::Left, times: Times::Double },
+    Movement { direction: Direction::Left, times: Times::CounterClockwise },
+    Movement { direction: Direction::Back, times: Times::Clockwise },
+    Movement { direction: Direction::Back, times: Times::Double },
+    Movement { direction: Direction::Back, times: Times::CounterClockwise },
+];
+
+/// Write the movements separated by spaces, as in `R U R' U'`.
+pub fn format_movements(movements: &[Movement]) -> String {
+    movements.iter().map(|m| m.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+/// Would turning `face` after `faces` be wasted?  That is a face turned
+/// right after itself, or turned again with only the opposite face in
+/// between, as in `R L R`.  Faces are numbered as in `FACE_TURNS`.
+fn redundant(faces: &[usize], face: usize) -> bool {
+    match *faces {
+        [.., last] if last == face => true,
+        [.., before, last] => before == face && last % 3 == face % 3,
+        _ => false,
+    }
+}
+
+/// `length` random face turns without any that are wasted, so no
+/// two turns of a face are next to each other even when an opposite
+/// face turns in between.
+pub fn random_turns<R: Rng>(rng: &mut R, length: usize) -> Vec<Movement> {
+    let mut faces = Vec::with_capacity(length);
+    let mut movements = Vec::with_capacity(length);
+    while movements.len() < length {
+        let m = rng.gen_range(0, FACE_TURNS.len());
+        if !redundant(&faces, m / 3) {
+            faces.push(m / 3);
+            movements.push(FACE_TURNS[m]);
+        }
+    }
+    movements
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{SeedableRng, StdRng};
+
+    fn rng(seed: usize) -> StdRng {
+        SeedableRng::from_seed(&[seed][..])
+    }
+
+    #[test]
+    fn test_random_turns() {
+        let movements = random_turns(&mut rng(1), 1000);
+        assert_eq!(1000, movements.len());
+        assert_eq!(movements, random_turns(&mut rng(1), 1000));
+        let faces: Vec<usize> = movements.iter()
+            .map(|m| FACE_TURNS.iter().position(|t| t == m).unwrap() / 3)
+            .collect();
+        for i in 1..faces.len() {
+            assert!(!redundant(&faces[..i], faces[i]));
+            assert!(faces[i] != faces[i - 1]);
+        }
+        assert!(redundant(&[1, 4], 1));
+        assert!(!redundant(&[1, 2], 1));
+    }
+}
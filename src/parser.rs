@@ -0,0 +1,394 @@
+use std::error;
+use std::fmt;
+use movement::{Direction, Times, Movement};
+
+/// The most times a group may be repeated.
+pub const MAX_REPETITIONS: usize = 1000;
+/// The most movements a sequence may expand to.  Repeating nested
+/// groups multiplies their lengths, so this bounds them all together.
+pub const MAX_MOVEMENTS: usize = 100_000;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ParseErrorKind {
+    /// The token is not a movement we know about.
+    InvalidMove,
+    /// A `(` or `[` is never closed.
+    UnclosedGroup,
+    /// A `)` or `]` has no matching `(` or `[`.
+    UnexpectedClose,
+    /// A `,` or `:` is outside of a `[` or is repeated.
+    UnexpectedSeparator,
+    /// A `[` is closed before a `,` or `:`.
+    MissingSeparator,
+    /// A group is repeated more than `MAX_REPETITIONS` times, or would
+    /// make the sequence longer than `MAX_MOVEMENTS`.
+    InvalidRepetition,
+}
+
+/// An error found while parsing a sequence of movements.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// The column the token starts at, counting from 1.
+    pub column: usize,
+    /// The offending token.
+    pub token: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self.kind {
+            ParseErrorKind::InvalidMove => "invalid move",
+            ParseErrorKind::UnclosedGroup => "unclosed group",
+            ParseErrorKind::UnexpectedClose => "unexpected",
+            ParseErrorKind::UnexpectedSeparator => "unexpected separator",
+            ParseErrorKind::MissingSeparator => "expected `,` or `:` before",
+            ParseErrorKind::InvalidRepetition => "invalid repetition",
+        };
+        write!(fmt, "{} `{}` at column {}", message, self.token, self.column)
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// Parse a sequence of movements written in Singmaster notation.
+///
+/// Faces are `F`, `B`, `L`, `R`, `U` and `D`, the slices are `M`, `E`
+/// and `S` and whole cube rotations are `x`, `y` and `z`.  Wide moves
+/// are written in lowercase, as in `r`, or with a `w`, as in `Rw`.
+/// Each may be followed by `'` to turn counter clockwise or by `2` to
+/// turn twice.
+///
+/// Movements may be separated by whitespace and grouped with
+/// parentheses, as in `(R U R' U') R2`.  A group followed by a number
+/// is repeated that many times, as in `(R U R' U')3`.  `[A, B]` is the
+/// commutator `A B A' B'` and `[A: B]` is the conjugate `A B A'`.
+pub fn parse(input: &str) -> Result<Vec<Movement>, ParseError> {
+    let mut parser = Parser { chars: input.chars().collect(), pos: 0 };
+    let movements = parser.parse_sequence()?;
+    if parser.peek().is_some() {
+        return Err(parser.unexpected());
+    }
+    Ok(movements)
+}
+
+fn inverse(movements: &[Movement]) -> Vec<Movement> {
+    movements.iter().rev().map(Movement::inverse).collect()
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self, kind: ParseErrorKind, start: usize, end: usize) -> ParseError {
+        ParseError {
+            kind,
+            column: start + 1,
+            token: self.chars[start..end].iter().collect(),
+        }
+    }
+
+    /// The error for a closing bracket or separator that we stopped
+    /// parsing a sequence at but cannot accept.
+    fn unexpected(&self) -> ParseError {
+        let kind = match self.peek() {
+            Some(',') | Some(':') => ParseErrorKind::UnexpectedSeparator,
+            _ => ParseErrorKind::UnexpectedClose,
+        };
+        self.error(kind, self.pos, self.pos + 1)
+    }
+
+    /// Consume `close`, or fail for the group opened at `start`.
+    fn expect_close(&mut self, close: char, start: usize) -> Result<(), ParseError> {
+        match self.peek() {
+            Some(c) if c == close => {
+                self.pos += 1;
+                Ok(())
+            },
+            None => Err(self.error(ParseErrorKind::UnclosedGroup, start, start + 1)),
+            Some(_) => Err(self.unexpected()),
+        }
+    }
+
+    /// Parse movements until the end of the input, a closing bracket
+    /// or a separator.
+    fn parse_sequence(&mut self) -> Result<Vec<Movement>, ParseError> {
+        let mut movements = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None | Some(')') | Some(']') | Some(',') | Some(':') => return Ok(movements),
+                Some('(') => {
+                    let start = self.pos;
+                    self.pos += 1;
+                    let group = self.parse_sequence()?;
+                    self.expect_close(')', start)?;
+                    let group = self.parse_repetition(group, start, movements.len())?;
+                    movements.extend(group);
+                },
+                Some('[') => {
+                    let start = self.pos;
+                    self.pos += 1;
+                    let a = self.parse_sequence()?;
+                    let separator = match self.peek() {
+                        Some(']') => return Err(self.error(ParseErrorKind::MissingSeparator,
+                                                           self.pos, self.pos + 1)),
+                        None => return Err(self.error(ParseErrorKind::UnclosedGroup,
+                                                      start, start + 1)),
+                        Some(c) => c,
+                    };
+                    self.pos += 1;
+                    let b = self.parse_sequence()?;
+                    self.expect_close(']', start)?;
+                    let mut group = a.clone();
+                    group.extend(b.iter().cloned());
+                    group.extend(inverse(&a));
+                    if separator == ',' {
+                        group.extend(inverse(&b));
+                    }
+                    let group = self.parse_repetition(group, start, movements.len())?;
+                    movements.extend(group);
+                },
+                Some(_) => movements.push(self.parse_movement()?),
+            }
+        }
+    }
+
+    /// Repeat the group opened at `group_start` by the number following
+    /// it, if there is one.  It is added to a sequence that already has
+    /// `before` movements.
+    fn parse_repetition(&mut self, group: Vec<Movement>, group_start: usize, before: usize)
+                        -> Result<Vec<Movement>, ParseError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let too_long = |times: usize| group.len().checked_mul(times)
+            .and_then(|length| length.checked_add(before))
+            .is_none_or(|length| length > MAX_MOVEMENTS);
+        if start == self.pos {
+            if too_long(1) {
+                return Err(self.error(ParseErrorKind::InvalidRepetition, group_start, self.pos));
+            }
+            return Ok(group);
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        match digits.parse::<usize>() {
+            Ok(times) if times <= MAX_REPETITIONS && !too_long(times) => {
+                let mut movements = Vec::with_capacity(group.len() * times);
+                for _ in 0..times {
+                    movements.extend(group.iter().cloned());
+                }
+                Ok(movements)
+            },
+            _ => Err(self.error(ParseErrorKind::InvalidRepetition, start, self.pos)),
+        }
+    }
+
+    fn parse_movement(&mut self) -> Result<Movement, ParseError> {
+        let start = self.pos;
+        let wide = self.chars.get(start + 1) == Some(&'w');
+        let letters = if wide { 2 } else { 1 };
+        let direction = match self.chars[start] {
+            'F' if wide => Some(Direction::WideFront),
+            'B' if wide => Some(Direction::WideBack),
+            'L' if wide => Some(Direction::WideLeft),
+            'R' if wide => Some(Direction::WideRight),
+            'U' if wide => Some(Direction::WideTop),
+            'D' if wide => Some(Direction::WideBottom),
+            _ if wide => None,
+            'F' => Some(Direction::Front),
+            'B' => Some(Direction::Back),
+            'L' => Some(Direction::Left),
+            'R' => Some(Direction::Right),
+            'U' => Some(Direction::Top),
+            'D' => Some(Direction::Bottom),
+            'f' => Some(Direction::WideFront),
+            'b' => Some(Direction::WideBack),
+            'l' => Some(Direction::WideLeft),
+            'r' => Some(Direction::WideRight),
+            'u' => Some(Direction::WideTop),
+            'd' => Some(Direction::WideBottom),
+            'M' => Some(Direction::Middle),
+            'E' => Some(Direction::Equator),
+            'S' => Some(Direction::Standing),
+            'x' => Some(Direction::X),
+            'y' => Some(Direction::Y),
+            'z' => Some(Direction::Z),
+            _ => None,
+        };
+        self.pos += letters;
+        while self.peek().is_some_and(|c| c == '\'' || c == '’' || c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let suffix: String = self.chars[start + letters..self.pos].iter().collect();
+        let times = match &*suffix {
+            "" => Some(Times::Clockwise),
+            "'" | "’" => Some(Times::CounterClockwise),
+            "2" | "2'" | "2’" => Some(Times::Double),
+            _ => None,
+        };
+        match (direction, times) {
+            (Some(direction), Some(times)) => Ok(Movement::new(direction, times)),
+            _ => Err(self.error(ParseErrorKind::InvalidMove, start, self.pos)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Ok(vec![Movement::new(Direction::Right, Times::Clockwise),
+                           Movement::new(Direction::Top, Times::CounterClockwise),
+                           Movement::new(Direction::Front, Times::Double),
+                           Movement::new(Direction::Middle, Times::Double)]),
+                   parse("R U' F2 M2'"));
+        assert_eq!(Ok(vec![Movement::new(Direction::Left, Times::Clockwise),
+                           Movement::new(Direction::Bottom, Times::CounterClockwise),
+                           Movement::new(Direction::Back, Times::Clockwise)]),
+                   parse("LD'B"));
+        assert_eq!(Ok(vec![Movement::new(Direction::X, Times::Clockwise),
+                           Movement::new(Direction::Y, Times::Double),
+                           Movement::new(Direction::Z, Times::CounterClockwise)]),
+                   parse("x y2 z'"));
+        assert_eq!(Ok(vec![Movement::new(Direction::WideRight, Times::Clockwise),
+                           Movement::new(Direction::WideRight, Times::CounterClockwise),
+                           Movement::new(Direction::WideTop, Times::Double),
+                           Movement::new(Direction::WideBottom, Times::Double)]),
+                   parse("r Rw' u2 Dw2"));
+        assert_eq!(Ok(vec![]), parse("  \n"));
+    }
+
+    #[test]
+    fn test_parse_groups() {
+        assert_eq!(parse("R U R' U' D"), parse("(R U) (R' U') D"));
+        assert_eq!(parse("R U R' U' D"), parse("((R U)(R' U'))D"));
+        assert_eq!(Ok(vec![]), parse("()"));
+    }
+
+    #[test]
+    fn test_parse_repetition() {
+        assert_eq!(parse("R U R' U' R U R' U' R U R' U'"), parse("(R U R' U')3"));
+        assert_eq!(parse("R2 F R2 F"), parse("(R2 F)2"));
+        assert_eq!(Ok(vec![]), parse("(R U)0"));
+    }
+
+    #[test]
+    fn test_parse_commutators() {
+        assert_eq!(parse("R U R' U'"), parse("[R, U]"));
+        assert_eq!(parse("F R U R' U' F'"), parse("[F: R U R' U']"));
+        assert_eq!(parse("F R U R' U' F'"), parse("[F:[R,U]]"));
+        assert_eq!(parse("R U2 R' D R U2 R' D'"), parse("[R U2 R', D]"));
+        assert_eq!(parse("R U R' U' R U R' U'"), parse("[R, U]2"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Err(ParseError {
+                       kind: ParseErrorKind::InvalidMove,
+                       column: 3,
+                       token: "X".to_string(),
+                   }),
+                   parse("R X U"));
+        assert_eq!(Err(ParseError {
+                       kind: ParseErrorKind::InvalidMove,
+                       column: 4,
+                       token: "U3".to_string(),
+                   }),
+                   parse("R2 U3"));
+        assert_eq!(Err(ParseError {
+                       kind: ParseErrorKind::InvalidMove,
+                       column: 3,
+                       token: "Mw'".to_string(),
+                   }),
+                   parse("R Mw'"));
+        assert_eq!(Err(ParseError {
+                       kind: ParseErrorKind::UnclosedGroup,
+                       column: 3,
+                       token: "(".to_string(),
+                   }),
+                   parse("R (U R'"));
+        assert_eq!(Err(ParseError {
+                       kind: ParseErrorKind::UnexpectedClose,
+                       column: 4,
+                       token: ")".to_string(),
+                   }),
+                   parse("R U) R'"));
+        assert_eq!(Err(ParseError {
+                       kind: ParseErrorKind::UnexpectedClose,
+                       column: 5,
+                       token: "]".to_string(),
+                   }),
+                   parse("(R U] R'"));
+        assert_eq!(Err(ParseError {
+                       kind: ParseErrorKind::MissingSeparator,
+                       column: 5,
+                       token: "]".to_string(),
+                   }),
+                   parse("[R U]"));
+        assert_eq!(Err(ParseError {
+                       kind: ParseErrorKind::UnexpectedSeparator,
+                       column: 6,
+                       token: ",".to_string(),
+                   }),
+                   parse("[R, U, F]"));
+        assert_eq!(Err(ParseError {
+                       kind: ParseErrorKind::UnexpectedSeparator,
+                       column: 2,
+                       token: ":".to_string(),
+                   }),
+                   parse("R: U"));
+        assert_eq!(Err(ParseError {
+                       kind: ParseErrorKind::UnclosedGroup,
+                       column: 1,
+                       token: "[".to_string(),
+                   }),
+                   parse("[R, U"));
+        assert_eq!(Err(ParseError {
+                       kind: ParseErrorKind::InvalidRepetition,
+                       column: 6,
+                       token: "1001".to_string(),
+                   }),
+                   parse("(R U)1001"));
+        assert_eq!(Err(ParseError {
+                       kind: ParseErrorKind::InvalidRepetition,
+                       column: 14,
+                       token: "1000".to_string(),
+                   }),
+                   parse("((((R U)1000)1000)1000)"));
+        assert_eq!(Err(ParseError {
+                       kind: ParseErrorKind::InvalidRepetition,
+                       column: 27,
+                       token: "1000".to_string(),
+                   }),
+                   parse("((R U)500)100 [R, (U)1000]1000"));
+        assert_eq!(Err(ParseError {
+                       kind: ParseErrorKind::InvalidRepetition,
+                       column: 14,
+                       token: "(R)".to_string(),
+                   }),
+                   parse("((R)1000)100 (R)"));
+        assert_eq!(MAX_MOVEMENTS, parse("((R)1000)100").unwrap().len());
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let movements = parse("R U' F2 M L' D B2 r' x2").unwrap();
+        assert_eq!("R U' F2 M L' D B2 r' x2", ::movement::format_movements(&movements));
+    }
+}
@@ -1,23 +1,349 @@
-use cube::Cube;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use color::Color;
+use cube::{Cube, FRONT, BACK, LEFT, RIGHT, TOP, BOTTOM};
 use corner::Corner;
 use side::Side;
-use color::Color;
+use cubies::{self, Cubies};
+use movement::{Direction, Movement, Times, FACE_TURNS};
+use parser::parse;
+use coordinates::in_thistlethwaite_group;
 
+/// The pieces are compared against the centers, so this works no
+/// matter how the cube is held.
 pub fn num_bottom_solved(cube: &Cube) -> usize {
-    8 - [cube.bottom_corners[0] == Corner::new(Color::Orange, Color::Blue, Color::White),
-         cube.bottom_corners[1] == Corner::new(Color::Orange, Color::White, Color::Green),
-         cube.bottom_corners[2] == Corner::new(Color::Orange, Color::Green, Color::Yellow),
-         cube.bottom_corners[3] == Corner::new(Color::Orange, Color::Yellow, Color::Blue),
-         cube.bottom_sides[0]   == Side::new(Color::Orange, Color::White),
-         cube.bottom_sides[1]   == Side::new(Color::Orange, Color::Green),
-         cube.bottom_sides[2]   == Side::new(Color::Orange, Color::Yellow),
-         cube.bottom_sides[3]   == Side::new(Color::Orange, Color::Blue)]
-        .into_iter().filter(|x| **x).count()
+    let c = &cube.centers;
+    8 - [cube.bottom_corners[0] == Corner::new(c[BOTTOM], c[LEFT], c[FRONT]),
+         cube.bottom_corners[1] == Corner::new(c[BOTTOM], c[FRONT], c[RIGHT]),
+         cube.bottom_corners[2] == Corner::new(c[BOTTOM], c[RIGHT], c[BACK]),
+         cube.bottom_corners[3] == Corner::new(c[BOTTOM], c[BACK], c[LEFT]),
+         cube.bottom_sides[0]   == Side::new(c[BOTTOM], c[FRONT]),
+         cube.bottom_sides[1]   == Side::new(c[BOTTOM], c[RIGHT]),
+         cube.bottom_sides[2]   == Side::new(c[BOTTOM], c[BACK]),
+         cube.bottom_sides[3]   == Side::new(c[BOTTOM], c[LEFT])]
+        .iter().filter(|x| **x).count()
+}
+
+/// Are the four bottom sides in place?
+pub fn bottom_cross_solved(cube: &Cube) -> bool {
+    cube.bottom_sides == Cube::solved_with_centers(cube.centers).bottom_sides
+}
+
+/// Is the bottom layer in place?
+pub fn bottom_solved(cube: &Cube) -> bool {
+    num_bottom_solved(cube) == 0
+}
+
+/// Are the bottom layer and the middle sides in place?
+pub fn middle_solved(cube: &Cube) -> bool {
+    bottom_solved(cube) &&
+        cube.middle_sides == Cube::solved_with_centers(cube.centers).middle_sides
+}
+
+/// Do the top sides all show the top color on the top?
+pub fn top_cross_solved(cube: &Cube) -> bool {
+    cube.top_sides.iter().all(|s| s.color(0) == cube.centers[TOP])
+}
+
+/// Is the top face all one color?
+pub fn top_oriented(cube: &Cube) -> bool {
+    top_cross_solved(cube) &&
+        cube.top_corners.iter().all(|c| c.color(0) == cube.centers[TOP])
+}
+
+/// Are the top corners in place?  The top sides may still be
+/// swapped.
+pub fn top_corners_solved(cube: &Cube) -> bool {
+    cube.top_corners == Cube::solved_with_centers(cube.centers).top_corners
+}
+
+fn in_group(cube: &Cube, phase: usize) -> bool {
+    Cubies::from_cube(cube).ok()
+        .is_some_and(|c| c.validate().is_ok() && in_thistlethwaite_group(&c, phase))
+}
+
+/// Is the cube in Thistlethwaite's G1, `<L, R, F, B, U2, D2>`?  Every
+/// side is oriented so that only quarter turns of the top and bottom
+/// would flip it.
+pub fn in_g1(cube: &Cube) -> bool {
+    in_group(cube, 1)
+}
+
+/// Is the cube in Thistlethwaite's G2, `<L, R, F2, B2, U2, D2>`?  The
+/// corners are also oriented by their left and right colors and the
+/// sides between the left and right faces are in that slice.
+pub fn in_g2(cube: &Cube) -> bool {
+    in_group(cube, 2)
+}
+
+/// Is the cube in Thistlethwaite's G3, `<L2, R2, F2, B2, U2, D2>`?
+pub fn in_g3(cube: &Cube) -> bool {
+    in_group(cube, 3)
+}
+
+/// The side slots around each center, indexed like `Cube::centers`.
+const CROSS_SIDES: [[usize; 4]; 6] = [
+    [0, 4, 7, 8], [2, 5, 6, 10], [1, 4, 5, 11], [3, 6, 7, 9], [0, 1, 2, 3], [8, 9, 10, 11],
+];
+
+/// Are the four sides around the center of `color` in place?  This is
+/// false if no center has that color.
+pub fn cross_solved(cube: &Cube, color: Color) -> bool {
+    let solved = Cube::solved_with_centers(cube.centers);
+    cube.centers.iter().position(|&c| c == color).is_some_and(|face| {
+        CROSS_SIDES[face].iter()
+            .all(|&slot| cubies::side(cube, slot) == cubies::side(&solved, slot))
+    })
+}
+
+/// The corner and side slot of each pair in the first two layers, in
+/// the same order as `Cube::bottom_corners`.
+pub const F2L_SLOTS: [(usize, usize); 4] = [(4, 4), (5, 7), (6, 6), (7, 5)];
+
+/// Which pairs of a bottom corner and the middle side above it are in
+/// place.  They are in the same order as `Cube::bottom_corners`: front
+/// left, front right, back right and back left.  The bottom cross does
+/// not have to be solved.
+pub fn f2l_pairs_solved(cube: &Cube) -> [bool; 4] {
+    let solved = Cube::solved_with_centers(cube.centers);
+    let mut pairs = [false; 4];
+    for (pair, &(corner, side)) in pairs.iter_mut().zip(F2L_SLOTS.iter()) {
+        *pair = cubies::corner(cube, corner) == cubies::corner(&solved, corner) &&
+            cubies::side(cube, side) == cubies::side(&solved, side);
+    }
+    pairs
+}
+
+pub fn num_f2l_pairs_solved(cube: &Cube) -> usize {
+    f2l_pairs_solved(cube).iter().filter(|x| **x).count()
+}
+
+/// Are the first two layers solved and the top face all one color?
+pub fn oll_solved(cube: &Cube) -> bool {
+    middle_solved(cube) && top_oriented(cube)
+}
+
+/// An algorithm for each OLL case, numbered from 1.  None of them
+/// rotate the cube.
+pub const OLL_ALGORITHMS: [&str; 57] = [
+    "R U2 R2 F R F' U2 R' F R F'",
+    "F R U R' U' F' f R U R' U' f'",
+    "f R U R' U' f' U' F R U R' U' F'",
+    "f R U R' U' f' U F R U R' U' F'",
+    "r' U2 R U R' U r",
+    "r U2 R' U' R U' r'",
+    "r U R' U R U2 r'",
+    "l' U' L U' L' U2 l",
+    "R U R' U' R' F R2 U R' U' F'",
+    "R U R' U R' F R F' R U2 R'",
+    "r U R' U R' F R F' R U2 r'",
+    "M' R' U' R U' R' U2 R U' M",
+    "F U R U' R2 F' R U R U' R'",
+    "R' F R U R' F' R F U' F'",
+    "r' U' r R' U' R U r' U r",
+    "r U r' R U R' U' r U' r'",
+    "R U R' U R' F R F' U2 R' F R F'",
+    "r U R' U R U2 r2 U' R U' R' U2 r",
+    "M U R U R' U' M' R' F R F'",
+    "r U R' U' M2 U R U' R' U' M'",
+    "R U2 R' U' R U R' U' R U' R'",
+    "R U2 R2 U' R2 U' R2 U2 R",
+    "R2 D' R U2 R' D R U2 R",
+    "r U R' U' r' F R F'",
+    "F' r U R' U' r' F R",
+    "R U2 R' U' R U' R'",
+    "R U R' U R U2 R'",
+    "r U R' U' M U R U' R'",
+    "R U R' U' R U' R' F' U' F R U R'",
+    "F R' F R2 U' R' U' R U R' F2",
+    "R' U' F U R U' R' F' R",
+    "L U F' U' L' U L F L'",
+    "R U R' U' R' F R F'",
+    "R U R2 U' R' F R U R U' F'",
+    "R U2 R2 F R F' R U2 R'",
+    "L' U' L U' L' U L U L F' L' F",
+    "F R' F' R U R U' R'",
+    "R U R' U R U' R' U' R' F R F'",
+    "L F' L' U' L U F U' L'",
+    "R' F R U R' U' F' U R",
+    "R U R' U R U2 R' F R U R' U' F'",
+    "R' U' R U' R' U2 R F R U R' U' F'",
+    "f' L' U' L U f",
+    "f R U R' U' f'",
+    "F R U R' U' F'",
+    "R' U' R' F R F' U R",
+    "R' U' R' F R F' R' F R F' U R",
+    "F R U R' U' R U R' U' F'",
+    "r U' r2 U r2 U r2 U' r",
+    "r' U r2 U' r2 U' r2 U r'",
+    "F U R U' R' U R U' R' F'",
+    "R U R' U R U' B U' B' R'",
+    "l' U2 L U L' U' L U L' U l",
+    "r U2 R' U' R U R' U' R U' r'",
+    "R' F R U R U' R2 F' R2 U' R' U R U R'",
+    "r' U' r U' R' U R U' R' U R r' U r",
+    "R U R' U' M' U R U' r'",
+];
+
+/// The numbers of the OLL cases whose top sides are already oriented,
+/// so only the corners need twisting.
+pub const OCLL_CASES: [usize; 7] = [21, 22, 23, 24, 25, 26, 27];
+
+/// The name of each PLL case and an algorithm for it.  None of them
+/// rotate the cube.
+pub const PLL_ALGORITHMS: [(&str, &str); 21] = [
+    ("Aa", "x R' U R' D2 R U' R' D2 R2 x'"),
+    ("Ab", "x R2 D2 R U R' D2 R U' R x'"),
+    ("E", "x' R U' R' D R U R' D' R U R' D R U' R' D' x"),
+    ("F", "R' U' F' R U R' U' R' F R2 U' R' U' R U R' U R"),
+    ("Ga", "R2 U R' U R' U' R U' R2 U' D R' U R D'"),
+    ("Gb", "R' U' R U D' R2 U R' U R U' R U' R2 D"),
+    ("Gc", "R2 U' R U' R U R' U R2 U D' R U' R' D"),
+    ("Gd", "R U R' U' D R2 U' R U' R' U R' U R2 D'"),
+    ("H", "M2 U M2 U2 M2 U M2"),
+    ("Ja", "R' U L' U2 R U' R' U2 R L"),
+    ("Jb", "R U R' F' R U R' U' R' F R2 U' R'"),
+    ("Na", "R U R' U R U R' F' R U R' U' R' F R2 U' R' U2 R U' R'"),
+    ("Nb", "R' U R U' R' F' U' F R U R' F R' F' R U' R"),
+    ("Ra", "R U' R' U' R U R D R' U' R D' R' U2 R'"),
+    ("Rb", "R2 F R U R U' R' F' R U2 R' U2 R"),
+    ("T", "R U R' U' R' F R2 U' R' U' R U R' F'"),
+    ("Ua", "M2 U M U2 M' U M2"),
+    ("Ub", "M2 U' M U2 M' U' M2"),
+    ("V", "R U' R U R' D R D' R U' D R2 U R2 D' R2"),
+    ("Y", "F R U' R' U' R U R' F' R U R' U' R' F R F'"),
+    ("Z", "M' U M2 U M2 U M' U2 M2"),
+];
+
+/// An OLL case and the turn of the top face that lines it up with its
+/// algorithm.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct OllCase {
+    /// From 1 to 57.
+    pub number: usize,
+    /// Turn the top face this way before the algorithm.
+    pub before: Option<Movement>,
+}
+
+impl OllCase {
+    /// The algorithm for the case from `OLL_ALGORITHMS`.
+    pub fn algorithm(&self) -> Vec<Movement> {
+        parse(OLL_ALGORITHMS[self.number - 1]).unwrap()
+    }
+}
+
+/// A PLL case and the turns of the top face needed around its
+/// algorithm.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct PllCase {
+    /// The name from `PLL_ALGORITHMS`, such as `"Ua"`.
+    pub name: &'static str,
+    /// Turn the top face this way before the algorithm.
+    pub before: Option<Movement>,
+    /// Turn the top face this way after the algorithm.
+    pub after: Option<Movement>,
+}
+
+impl PllCase {
+    /// The algorithm for the case from `PLL_ALGORITHMS`.
+    pub fn algorithm(&self) -> Vec<Movement> {
+        let &(_, algorithm) = PLL_ALGORITHMS.iter().find(|&&(n, _)| n == self.name).unwrap();
+        parse(algorithm).unwrap()
+    }
+}
+
+fn top_turn(quarter_turns: usize) -> Option<Movement> {
+    Times::from_quarter_turns(quarter_turns as u8).map(|t| Movement::new(Direction::Top, t))
+}
+
+/// The cube that `algorithm` solves, with `before` and `after` turns
+/// of the top face around it.
+fn case_cubies(algorithm: &str, before: usize, after: usize) -> Cubies {
+    let mut movements = Vec::new();
+    movements.extend(top_turn(before));
+    movements.extend(parse(algorithm).unwrap());
+    movements.extend(top_turn(after));
+    let inverse: Vec<Movement> = movements.iter().rev().map(Movement::inverse).collect();
+    let mut cube = Cube::new();
+    cube.apply_all(&inverse);
+    Cubies::from_cube(&cube).unwrap()
+}
+
+/// How the top pieces are turned, which is all OLL looks at.
+fn top_orientation(cubies: &Cubies) -> [u8; 8] {
+    let mut orientation = [0; 8];
+    orientation[..4].copy_from_slice(&cubies.corner_orientation[..4]);
+    orientation[4..].copy_from_slice(&cubies.side_orientation[..4]);
+    orientation
+}
+
+fn oll_table() -> &'static HashMap<[u8; 8], usize> {
+    static TABLE: OnceLock<HashMap<[u8; 8], usize>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = HashMap::new();
+        for (i, algorithm) in OLL_ALGORITHMS.iter().enumerate() {
+            table.entry(top_orientation(&case_cubies(algorithm, 0, 0))).or_insert(i + 1);
+        }
+        table
+    })
+}
+
+/// Every cube one PLL algorithm away from solved, with the case and the
+/// quarter turns of the top face before and after the algorithm.
+fn pll_table() -> &'static HashMap<Cubies, (usize, usize, usize)> {
+    static TABLE: OnceLock<HashMap<Cubies, (usize, usize, usize)>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = HashMap::new();
+        for (i, &(_, algorithm)) in PLL_ALGORITHMS.iter().enumerate() {
+            for before in 0..4 {
+                for after in 0..4 {
+                    table.entry(case_cubies(algorithm, before, after))
+                        .or_insert((i, before, after));
+                }
+            }
+        }
+        table
+    })
+}
+
+/// Name the OLL case of a cube with its first two layers solved.  This
+/// is `None` before then and once the top is oriented.
+pub fn oll_case(cube: &Cube) -> Option<OllCase> {
+    if !middle_solved(cube) || top_oriented(cube) {
+        return None;
+    }
+    let mut cubies = Cubies::from_cube(cube).ok()?;
+    for turns in 0..4 {
+        if let Some(&number) = oll_table().get(&top_orientation(&cubies)) {
+            return Some(OllCase { number, before: top_turn(turns) });
+        }
+        assert!(cubies.apply(&FACE_TURNS[0]));
+    }
+    None
+}
+
+/// Name the PLL case of a cube whose last layer is oriented.  This is
+/// `None` before then and once only a turn of the top face is left.
+pub fn pll_case(cube: &Cube) -> Option<PllCase> {
+    if !oll_solved(cube) {
+        return None;
+    }
+    let cubies = Cubies::from_cube(cube).ok()?;
+    pll_table().get(&cubies).map(|&(i, before, after)| PllCase {
+        name: PLL_ALGORITHMS[i].0,
+        before: top_turn(before),
+        after: top_turn(after),
+    })
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use algorithm::Algorithm;
+
+    fn inverse(algorithm: &str) -> Vec<Movement> {
+        algorithm.parse::<Algorithm>().unwrap().inverse().movements
+    }
 
     #[test]
     fn test_num_bottom_solved() {
@@ -38,4 +364,123 @@ mod test {
         cube.rotate_front_counter_clockwise();
         assert_eq!(0, num_bottom_solved(&cube));
     }
+
+    #[test]
+    fn test_num_bottom_solved_rotated() {
+        let mut cube = Cube::new();
+        cube.rotate_x_clockwise();
+        assert_eq!(0, num_bottom_solved(&cube));
+        cube.rotate_y_clockwise();
+        assert_eq!(0, num_bottom_solved(&cube));
+        cube.rotate_front_clockwise();
+        assert_eq!(3, num_bottom_solved(&cube));
+    }
+
+    #[test]
+    fn test_stages() {
+        let mut cube = Cube::new();
+        cube.rotate_top_clockwise();
+        assert!(middle_solved(&cube));
+        assert!(top_oriented(&cube));
+        assert!(!top_corners_solved(&cube));
+
+        cube.rotate_right_clockwise();
+        assert!(!bottom_cross_solved(&cube));
+        assert!(!top_cross_solved(&cube));
+
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("U R U' R' U' F' U F").unwrap());
+        assert!(bottom_solved(&cube));
+        assert!(!middle_solved(&cube));
+    }
+
+    #[test]
+    fn test_thistlethwaite_groups() {
+        let mut cube = Cube::new();
+        assert!(in_g3(&cube));
+        cube.apply_all(&parse("R2 U2 F2").unwrap());
+        assert!(in_g3(&cube));
+        cube.apply_all(&parse("R").unwrap());
+        assert!(in_g2(&cube) && !in_g3(&cube));
+        cube.apply_all(&parse("F").unwrap());
+        assert!(in_g1(&cube) && !in_g2(&cube));
+        cube.apply_all(&parse("U").unwrap());
+        assert!(!in_g1(&cube));
+    }
+
+    #[test]
+    fn test_cross_solved() {
+        let mut cube = Cube::new();
+        assert!(cube.centers.iter().all(|&c| cross_solved(&cube, c)));
+        cube.apply_all(&parse("R").unwrap());
+        assert!(cross_solved(&cube, Color::Blue));
+        assert!(!cross_solved(&cube, Color::Green));
+        assert!(!cross_solved(&cube, Color::White));
+        cube.apply_all(&parse("x y").unwrap());
+        assert!(cross_solved(&cube, Color::Blue));
+        assert!(!cross_solved(&cube, Color::Red));
+    }
+
+    #[test]
+    fn test_f2l_pairs_solved() {
+        let mut cube = Cube::new();
+        assert_eq!(4, num_f2l_pairs_solved(&cube));
+        cube.apply_all(&parse("R U R'").unwrap());
+        assert_eq!([true, false, true, true], f2l_pairs_solved(&cube));
+        cube.apply_all(&parse("L' U' L").unwrap());
+        assert_eq!([false, false, true, true], f2l_pairs_solved(&cube));
+        assert_eq!(2, num_f2l_pairs_solved(&cube));
+        assert!(!oll_solved(&cube));
+    }
+
+    #[test]
+    fn test_oll_case() {
+        for (i, algorithm) in OLL_ALGORITHMS.iter().enumerate() {
+            let mut cube = Cube::new();
+            cube.apply_all(&parse(algorithm).unwrap());
+            assert_eq!(Cube::new().centers, cube.centers, "{}", algorithm);
+            for &before in &["", "U", "U2", "U'"] {
+                let mut case = Cube::new();
+                case.apply_all(&inverse(&format!("{} {}", before, algorithm)));
+                let recognized = oll_case(&case).unwrap();
+                assert_eq!(i + 1, recognized.number);
+                case.apply_all(&recognized.before.into_iter().collect::<Vec<_>>());
+                case.apply_all(&recognized.algorithm());
+                assert!(oll_solved(&case), "{}", algorithm);
+            }
+        }
+        for (i, algorithm) in OLL_ALGORITHMS.iter().enumerate() {
+            let mut case = Cube::new();
+            case.apply_all(&inverse(algorithm));
+            assert_eq!(OCLL_CASES.contains(&(i + 1)), top_cross_solved(&case), "{}", algorithm);
+        }
+        assert_eq!(None, oll_case(&Cube::new()));
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("R U R'").unwrap());
+        assert_eq!(None, oll_case(&cube));
+    }
+
+    #[test]
+    fn test_pll_case() {
+        for &(name, algorithm) in PLL_ALGORITHMS.iter() {
+            let mut cube = Cube::new();
+            cube.apply_all(&parse(algorithm).unwrap());
+            assert_eq!(Cube::new().centers, cube.centers, "{}", algorithm);
+            for &(before, after) in &[("", ""), ("U", "U2"), ("U'", "U")] {
+                let mut case = Cube::new();
+                case.apply_all(&inverse(&format!("{} {} {}", before, algorithm, after)));
+                let recognized = pll_case(&case).unwrap();
+                assert_eq!(name, recognized.name);
+                case.apply_all(&recognized.before.into_iter().collect::<Vec<_>>());
+                case.apply_all(&recognized.algorithm());
+                case.apply_all(&recognized.after.into_iter().collect::<Vec<_>>());
+                assert!(case.is_solved(), "{}", algorithm);
+            }
+        }
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("U").unwrap());
+        assert_eq!(None, pll_case(&cube));
+        cube.apply_all(&parse("R U R' U R U2 R'").unwrap());
+        assert_eq!(None, pll_case(&cube));
+    }
 }
@@ -0,0 +1,88 @@
+//! Random-state scrambles, made the way competition scramblers make
+//! them: pick an arrangement of the pieces with every solvable one as
+//! likely, then find turns that lead to it.  `movement::random_turns`
+//! makes scrambles of random face turns instead.
+
+use rand::Rng;
+use coordinates::{orientation_from_index, CORNER_ORIENTATIONS, SIDE_ORIENTATIONS};
+use cube::Cube;
+use cubies::{is_odd, Cubies};
+use movement::Movement;
+use simplify::simplify;
+use solver::{SolveError, Solver};
+use solver::kociemba::Kociemba;
+use solver::thistlethwaite::Thistlethwaite;
+
+/// A solvable arrangement of the pieces chosen uniformly at random.
+pub fn random_cubies<R: Rng>(rng: &mut R) -> Cubies {
+    let mut cubies = Cubies::new();
+    rng.shuffle(&mut cubies.corner_permutation);
+    rng.shuffle(&mut cubies.side_permutation);
+    // Swapping two sides maps the arrangements of the wrong parity one
+    // to one onto those of the right parity, so they stay uniform.
+    if is_odd(&cubies.corner_permutation) != is_odd(&cubies.side_permutation) {
+        cubies.side_permutation.swap(0, 1);
+    }
+    orientation_from_index(rng.gen_range(0, CORNER_ORIENTATIONS), 3,
+                           &mut cubies.corner_orientation);
+    orientation_from_index(rng.gen_range(0, SIDE_ORIENTATIONS), 2,
+                           &mut cubies.side_orientation);
+    cubies
+}
+
+/// How many nodes `random_state` lets `Kociemba` search.  A budget of
+/// nodes rather than time keeps scrambles the same on any machine.
+const NODE_BUDGET: usize = 10_000_000;
+
+/// Face turns that take a solved cube to `random_cubies`.  They are
+/// found with `Kociemba`, so there are rarely more than 21, and the same
+/// generator state always gives the same turns.
+pub fn random_state<R: Rng>(rng: &mut R) -> Result<Vec<Movement>, SolveError> {
+    let cube = random_cubies(rng).to_cube(Cube::new().centers);
+    let solution = match Kociemba::with_node_budget(21, NODE_BUDGET).solve(&cube) {
+        Ok(solution) => solution,
+        Err(_) => Thistlethwaite::new().solve(&cube)?,
+    };
+    Ok(simplify(&solution.iter().rev().map(Movement::inverse).collect::<Vec<_>>()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{SeedableRng, StdRng};
+
+    fn rng(seed: usize) -> StdRng {
+        SeedableRng::from_seed(&[seed][..])
+    }
+
+    #[test]
+    fn test_random_cubies() {
+        let mut rng = rng(1);
+        let mut odd = 0;
+        let mut twisted = [0; 3];
+        for _ in 0..3000 {
+            let cubies = random_cubies(&mut rng);
+            assert_eq!(Ok(()), cubies.validate());
+            if is_odd(&cubies.corner_permutation) {
+                odd += 1;
+            }
+            twisted[cubies.corner_orientation[0] as usize] += 1;
+        }
+        assert!(odd > 1300 && odd < 1700);
+        assert!(twisted.iter().all(|&t| t > 850 && t < 1150));
+    }
+
+    #[test]
+    fn test_random_state() {
+        for seed in 0..2 {
+            let movements = random_state(&mut rng(seed)).unwrap();
+            assert!(movements.len() <= 30);
+            let mut cube = Cube::new();
+            cube.apply_all(&movements);
+            let expected = random_cubies(&mut rng(seed)).to_cube(Cube::new().centers);
+            assert_eq!(expected, cube);
+        }
+        assert_eq!(random_state(&mut rng(3)), random_state(&mut rng(3)));
+        assert!(random_state(&mut rng(3)) != random_state(&mut rng(4)));
+    }
+}
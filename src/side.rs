@@ -1,7 +1,7 @@
 use color::Color;
 
 /// Sides are described in clockwise fashion
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Side {
     colors: [Color; 2],
 }
@@ -16,4 +16,12 @@ impl Side {
     }
 
     pub fn color(&self, i: usize) -> Color { self.colors[i] }
+
+    /// The index of the color that is `top` or `bottom`, or if there
+    /// is none then the one that is `front` or `back`.
+    pub fn orientation(&self, top: Color, bottom: Color, front: Color, back: Color) -> Option<u8> {
+        self.colors.iter().position(|&c| c == top || c == bottom)
+            .or_else(|| self.colors.iter().position(|&c| c == front || c == back))
+            .map(|i| i as u8)
+    }
 }
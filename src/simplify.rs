@@ -0,0 +1,103 @@
+use movement::{Direction, Times, Movement, FACE_TURNS};
+
+/// Remove redundant movements without changing what the sequence
+/// does to the cube.
+///
+/// Consecutive turns in the same direction are merged, so `R R`
+/// becomes `R2`, `R2 R` becomes `R'` and `R R'` disappears.  Turns on
+/// the same axis commute, so they are merged even when other turns on
+/// that axis are between them: `R L R'` becomes `L`.  Turns on the same
+/// axis that end up next to each other are sorted, so `R L` and `L R`
+/// both become `R L`.
+pub fn simplify(movements: &[Movement]) -> Vec<Movement> {
+    let mut result: Vec<Movement> = Vec::with_capacity(movements.len());
+    for movement in movements {
+        push(&mut result, *movement);
+    }
+    result
+}
+
+/// Where turns in `direction` go among the others on their axis: the
+/// faces in the order of `FACE_TURNS`, then the slices, the wide turns
+/// and the rotations.
+fn rank(direction: Direction) -> usize {
+    match FACE_TURNS.iter().position(|m| m.direction == direction) {
+        Some(i) => i / 3,
+        None => 6 + direction as usize,
+    }
+}
+
+fn push(result: &mut Vec<Movement>, movement: Movement) {
+    let axis = movement.direction.axis();
+    // The trailing movements commute with this one, and are sorted.
+    let start = result.iter().rposition(|m| m.direction.axis() != axis).map_or(0, |i| i + 1);
+    if let Some(i) = (start..result.len()).find(|&i| result[i].direction == movement.direction) {
+        let quarter_turns = result[i].times.quarter_turns() + movement.times.quarter_turns();
+        match Times::from_quarter_turns(quarter_turns) {
+            Some(times) => result[i].times = times,
+            None => {
+                result.remove(i);
+            },
+        }
+        return;
+    }
+    let i = (start..result.len())
+        .find(|&i| rank(result[i].direction) > rank(movement.direction))
+        .unwrap_or(result.len());
+    result.insert(i, movement);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cube::Cube;
+    use parser::parse;
+
+    fn simplified(s: &str) -> String {
+        ::movement::format_movements(&simplify(&parse(s).unwrap()))
+    }
+
+    #[test]
+    fn test_merge() {
+        assert_eq!("R2", simplified("R R"));
+        assert_eq!("", simplified("R R'"));
+        assert_eq!("R'", simplified("R2 R"));
+        assert_eq!("R", simplified("R R R R R"));
+        assert_eq!("R U2 F'", simplified("R U U F' F2 F2"));
+    }
+
+    #[test]
+    fn test_cancel_across_axis() {
+        assert_eq!("L", simplified("R L R'"));
+        assert_eq!("L2 M x", simplified("R L M R' L x"));
+        assert_eq!("U2 D'", simplified("U E D' U2 E' U'"));
+        assert_eq!("R L U L'", simplified("R L U L'"));
+    }
+
+    #[test]
+    fn test_canonical_order() {
+        assert_eq!(simplified("R L"), simplified("L R"));
+        assert_eq!("R L", simplified("L R"));
+        assert_eq!("U D' R2 L", simplified("D' U L R2"));
+        assert_eq!("R L' M x", simplified("x M L' R"));
+        assert_eq!("R L", simplified("L U U' R"));
+    }
+
+    #[test]
+    fn test_cascading_cancellation() {
+        assert_eq!("", simplified("R U F F' U' R'"));
+        assert_eq!("B", simplified("R U L D D' L' U' R' B"));
+        assert_eq!("", simplified("[R U R', D] D R U R' D' R U' R'"));
+    }
+
+    #[test]
+    fn test_same_state() {
+        let movements = parse("R L' R2 M U D U' E2 f b' F r' l x R x' U2 U2 y z S").unwrap();
+        let mut expected = Cube::new();
+        expected.apply_all(&movements);
+        let mut cube = Cube::new();
+        cube.apply_all(&simplify(&movements));
+        assert_eq!(expected, cube);
+        assert!(simplify(&movements).len() < movements.len());
+    }
+}
@@ -1,10 +1,417 @@
+use std::collections::VecDeque;
+use std::error;
+use std::fmt;
 use cube::Cube;
-use corner::Corner;
-use side::Side;
-use color::Color;
+use cubies::{self, Cubies};
+use movement::{Direction, Movement, FACE_TURNS};
+use parser::parse;
+use simplify::simplify;
+use validation::ValidationError;
 
 use predicates::*;
 
-pub fn solve_bottom(cube: &mut Cube) {
-    
+pub mod benchmark;
+pub mod cache;
+pub mod cfop;
+pub mod kociemba;
+pub mod optimal;
+pub mod roux;
+pub mod thistlethwaite;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SolveError {
+    /// The cube cannot be solved.
+    Invalid(ValidationError),
+    /// There is no solution within the maximum number of moves.
+    TooLong,
+    /// No solution was found in the time or number of nodes allowed.
+    Timeout,
+    /// A stage of the method, named here, could not be finished.
+    StageFailed(&'static str),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SolveError::Invalid(ref error) => write!(fmt, "{}", error),
+            SolveError::TooLong => write!(fmt, "no solution is short enough"),
+            SolveError::Timeout => write!(fmt, "ran out of time"),
+            SolveError::StageFailed(stage) => write!(fmt, "could not solve the {}", stage),
+        }
+    }
+}
+
+impl error::Error for SolveError {}
+
+impl From<ValidationError> for SolveError {
+    fn from(error: ValidationError) -> Self {
+        SolveError::Invalid(error)
+    }
+}
+
+/// A way of solving cubes.  The solution is made relative to the
+/// cube's centers, so it solves the cube however it is held.
+pub trait Solver {
+    fn solve(&self, cube: &Cube) -> Result<Vec<Movement>, SolveError>;
+}
+
+/// Where a side is and whether it is flipped, as `slot * 2 + flip`,
+/// after each of `FACE_TURNS`.
+fn side_turns() -> Vec<[u8; 18]> {
+    let mut table = vec![[0; 18]; 24];
+    for (m, turn) in cubies::face_turns().iter().enumerate() {
+        for slot in 0..12 {
+            let from = turn.side_permutation[slot] as usize;
+            for flip in 0..2 {
+                let to = slot * 2 + (flip ^ turn.side_orientation[slot]) as usize;
+                table[from * 2 + flip as usize][m] = to as u8;
+            }
+        }
+    }
+    table
+}
+
+/// How many turns each piece state is from `home`, given where each
+/// state goes after each face turn.
+fn piece_distances(turns: &[[u8; 18]], home: usize) -> [u8; 24] {
+    let mut distances = [u8::MAX; 24];
+    distances[home] = 0;
+    let mut queue = VecDeque::new();
+    queue.push_back(home);
+    while let Some(state) = queue.pop_front() {
+        for &next in turns[state].iter() {
+            if distances[next as usize] == u8::MAX {
+                distances[next as usize] = distances[state] + 1;
+                queue.push_back(next as usize);
+            }
+        }
+    }
+    distances
+}
+
+/// Turns of the same face are never made twice in a row and turns of
+/// opposite faces are only made in one order.
+fn redundant(last: Option<usize>, m: usize) -> bool {
+    match last {
+        Some(last) => {
+            let (face, last_face) = (m / 3, last / 3);
+            face == last_face || (face % 3 == last_face % 3 && face < last_face)
+        },
+        None => false,
+    }
+}
+
+struct CrossSearch {
+    turns: Vec<[u8; 18]>,
+    distances: Vec<[u8; 24]>,
+    /// How many of the bottom sides have to be in place.
+    count: usize,
+}
+
+impl CrossSearch {
+    fn search(&self, states: [u8; 4], depth: usize, path: &mut Vec<usize>) -> bool {
+        let estimate = (0..self.count)
+            .map(|i| self.distances[i][states[i] as usize] as usize)
+            .max().unwrap();
+        if estimate == 0 {
+            return true;
+        }
+        if estimate > depth {
+            return false;
+        }
+        for m in 0..18 {
+            if redundant(path.last().cloned(), m) {
+                continue;
+            }
+            let mut next = states;
+            for state in next.iter_mut() {
+                *state = self.turns[*state as usize][m];
+            }
+            path.push(m);
+            if self.search(next, depth - 1, path) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+}
+
+/// Put the bottom sides in place one at a time.
+fn solve_cross(cube: &mut Cube) -> Result<Vec<Movement>, SolveError> {
+    let turns = side_turns();
+    let mut search = CrossSearch {
+        distances: (0..4).map(|i| piece_distances(&turns, (8 + i) * 2)).collect(),
+        turns,
+        count: 0,
+    };
+    let mut movements = Vec::new();
+    for count in 1..5 {
+        let cubies = Cubies::from_cube(cube)?;
+        let mut states = [0; 4];
+        for (i, state) in states.iter_mut().enumerate() {
+            let slot = cubies.side_permutation.iter().position(|&p| p as usize == 8 + i).unwrap();
+            *state = (slot * 2) as u8 + cubies.side_orientation[slot];
+        }
+        search.count = count;
+        let mut path = Vec::new();
+        let mut depth = 0;
+        while !search.search(states, depth, &mut path) {
+            depth += 1;
+        }
+        for &m in &path {
+            cube.apply(&FACE_TURNS[m]);
+            movements.push(FACE_TURNS[m]);
+        }
+    }
+    if !bottom_cross_solved(cube) {
+        return Err(SolveError::StageFailed("bottom cross"));
+    }
+    Ok(movements)
+}
+
+/// Do `movements` as if the cube had been turned clockwise from above
+/// `times` times, so an algorithm for the front right slot works on the
+/// back right one.
+fn around(movements: &[Movement], times: usize) -> Vec<Movement> {
+    movements.iter().map(|m| {
+        let mut direction = m.direction;
+        for _ in 0..times {
+            direction = match direction {
+                Direction::Right => Direction::Back,
+                Direction::Back => Direction::Left,
+                Direction::Left => Direction::Front,
+                Direction::Front => Direction::Right,
+                d => d,
+            };
+        }
+        Movement::new(direction, m.times)
+    }).collect()
+}
+
+fn apply(cube: &mut Cube, movements: &[Movement], solution: &mut Vec<Movement>) {
+    cube.apply_all(movements);
+    solution.extend_from_slice(movements);
+}
+
+/// Put the bottom corners in place.  Each corner is brought to the top
+/// above its slot and `R U R' U'` is repeated until it drops in.
+fn solve_bottom_corners(cube: &mut Cube) -> Result<Vec<Movement>, SolveError> {
+    // How far `around` to do the front right algorithm for each of
+    // `Cube::bottom_corners`.
+    const AROUND: [usize; 4] = [3, 0, 1, 2];
+    // The top slot above each bottom slot.
+    const ABOVE: [usize; 4] = [0, 3, 2, 1];
+    let insert = parse("R U R' U'").unwrap();
+    let top = parse("U").unwrap();
+    let position = |cube: &Cube, corner: usize| -> Result<(usize, u8), SolveError> {
+        let cubies = Cubies::from_cube(cube)?;
+        let slot = cubies.corner_permutation.iter().position(|&p| p as usize == corner).unwrap();
+        Ok((slot, cubies.corner_orientation[slot]))
+    };
+    let mut movements = Vec::new();
+    for i in 0..4 {
+        let corner = 4 + i;
+        let (slot, orientation) = position(cube, corner)?;
+        if slot == corner && orientation == 0 {
+            continue;
+        }
+        if slot >= 4 {
+            apply(cube, &around(&insert, AROUND[slot - 4]), &mut movements);
+        }
+        while position(cube, corner)?.0 != ABOVE[i] {
+            apply(cube, &top, &mut movements);
+        }
+        while position(cube, corner)? != (corner, 0) {
+            apply(cube, &around(&insert, AROUND[i]), &mut movements);
+        }
+    }
+    if !bottom_solved(cube) {
+        return Err(SolveError::StageFailed("bottom corners"));
+    }
+    Ok(movements)
+}
+
+/// Solve the bottom layer: first the cross and then the corners.  The
+/// cube must be valid; see `Cube::validate`.
+pub fn solve_bottom(cube: &mut Cube) -> Result<Vec<Movement>, SolveError> {
+    let mut movements = solve_cross(cube)?;
+    movements.extend(solve_bottom_corners(cube)?);
+    Ok(movements)
+}
+
+/// Find the fewest `algorithms`, up to `max_depth` of them, that reach
+/// `goal`.  The first three algorithms must be the turns of the top,
+/// which are never made twice in a row.
+fn search(cube: &Cube, algorithms: &[Vec<Movement>], max_depth: usize,
+          goal: &dyn Fn(&Cube) -> bool) -> Option<Vec<Movement>> {
+    fn search_depth(cube: &Cube, algorithms: &[Vec<Movement>], depth: usize,
+                    goal: &dyn Fn(&Cube) -> bool, path: &mut Vec<usize>) -> bool {
+        if goal(cube) {
+            return true;
+        }
+        if depth == 0 {
+            return false;
+        }
+        for (i, algorithm) in algorithms.iter().enumerate() {
+            if i < 3 && path.last().is_some_and(|&last| last < 3) {
+                continue;
+            }
+            let mut next = cube.clone();
+            next.apply_all(algorithm);
+            path.push(i);
+            if search_depth(&next, algorithms, depth - 1, goal, path) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    let mut path = Vec::new();
+    (0..max_depth + 1)
+        .find(|&depth| search_depth(cube, algorithms, depth, goal, &mut path))
+        .map(|_| path.iter().flat_map(|&i| algorithms[i].iter().cloned()).collect())
+}
+
+/// Run a `search` for the stage called `stage` and apply what it finds.
+fn solve_stage(cube: &mut Cube, stage: &'static str, algorithms: &[&str], max_depth: usize,
+               goal: &dyn Fn(&Cube) -> bool) -> Result<Vec<Movement>, SolveError> {
+    let mut all = vec![parse("U").unwrap(), parse("U'").unwrap(), parse("U2").unwrap()];
+    all.extend(algorithms.iter().map(|a| parse(a).unwrap()));
+    let movements = search(cube, &all, max_depth, goal).ok_or(SolveError::StageFailed(stage))?;
+    cube.apply_all(&movements);
+    Ok(movements)
+}
+
+/// Put the middle sides in place, taking each from the top or out of
+/// the wrong slot.
+fn solve_middle(cube: &mut Cube) -> Result<Vec<Movement>, SolveError> {
+    let right = parse("U R U' R' U' F' U F").unwrap();
+    let left = parse("U' L' U L U F U' F'").unwrap();
+    let mut algorithms = vec![parse("U").unwrap(), parse("U'").unwrap(), parse("U2").unwrap()];
+    for times in 0..4 {
+        algorithms.push(around(&right, times));
+        algorithms.push(around(&left, times));
+    }
+    let mut movements = Vec::new();
+    for i in 0..4 {
+        let goal = |cube: &Cube| {
+            let solved = Cube::solved_with_centers(cube.centers);
+            bottom_solved(cube) && cube.middle_sides[..i + 1] == solved.middle_sides[..i + 1]
+        };
+        let found = search(cube, &algorithms, 3, &goal)
+            .ok_or(SolveError::StageFailed("middle layer"))?;
+        apply(cube, &found, &mut movements);
+    }
+    if !middle_solved(cube) {
+        return Err(SolveError::StageFailed("middle layer"));
+    }
+    Ok(movements)
+}
+
+/// Solve the cube layer by layer as a beginner would: the bottom
+/// cross, the bottom corners, the middle sides, the top cross, the top
+/// face and finally the top corners and sides.  The cube is left solved
+/// and the movements made are returned.
+pub fn solve(cube: &mut Cube) -> Result<Vec<Movement>, SolveError> {
+    cube.validate()?;
+    let mut movements = solve_bottom(cube)?;
+    movements.extend(solve_middle(cube)?);
+    movements.extend(solve_stage(cube, "top cross", &["F R U R' U' F'"], 5,
+                                 &|c| middle_solved(c) && top_cross_solved(c))?);
+    movements.extend(solve_stage(cube, "top face", &["R U R' U R U2 R'"], 6,
+                                 &|c| middle_solved(c) && top_oriented(c))?);
+    movements.extend(solve_stage(cube, "top corners", &["R' F R' B2 R F' R' B2 R2"], 5,
+                                 &|c| middle_solved(c) && top_oriented(c) &&
+                                      top_corners_solved(c))?);
+    movements.extend(solve_stage(cube, "top sides", &["R U' R U R U R U' R' U' R2",
+                                                      "R2 U R U R' U' R' U' R' U R'"], 5,
+                                 &|c| c.is_solved_in_any_orientation())?);
+    Ok(simplify(&movements))
+}
+
+/// Solves cubes layer by layer with `solve`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Beginner;
+
+impl Beginner {
+    pub fn new() -> Self {
+        Beginner
+    }
+}
+
+impl Solver for Beginner {
+    fn solve(&self, cube: &Cube) -> Result<Vec<Movement>, SolveError> {
+        solve(&mut cube.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{Rng, SeedableRng, StdRng};
+
+    fn scrambled(seed: usize) -> Cube {
+        let mut rng: StdRng = SeedableRng::from_seed(&[seed][..]);
+        let mut cube = Cube::new();
+        for _ in 0..30 {
+            cube.apply(&FACE_TURNS[rng.gen_range(0, 18)]);
+        }
+        cube
+    }
+
+    #[test]
+    fn test_solve_bottom() {
+        let mut cube = scrambled(1);
+        let start = cube.clone();
+        let movements = solve_bottom(&mut cube).unwrap();
+        assert!(bottom_solved(&cube));
+        let mut replay = start;
+        replay.apply_all(&movements);
+        assert_eq!(cube, replay);
+    }
+
+    #[test]
+    fn test_solve() {
+        for seed in 0..5 {
+            let mut cube = scrambled(seed);
+            let start = cube.clone();
+            let movements = solve(&mut cube).unwrap();
+            assert!(cube.is_solved_in_any_orientation());
+            let mut replay = start;
+            replay.apply_all(&movements);
+            assert!(replay.is_solved_in_any_orientation());
+        }
+    }
+
+    #[test]
+    fn test_solve_rotated() {
+        let mut cube = scrambled(7);
+        cube.apply_all(&parse("x y2").unwrap());
+        solve(&mut cube).unwrap();
+        assert!(cube.is_solved_in_any_orientation());
+    }
+
+    #[test]
+    fn test_solve_solved() {
+        assert_eq!(Ok(vec![]), solve(&mut Cube::new()));
+    }
+
+    #[test]
+    fn test_beginner() {
+        let mut cube = scrambled(3);
+        let start = cube.clone();
+        let movements = Beginner::new().solve(&start).unwrap();
+        assert_eq!(start, cube);
+        cube.apply_all(&movements);
+        assert!(cube.is_solved());
+    }
+
+    #[test]
+    fn test_solve_invalid() {
+        let mut cube = Cube::new();
+        cube.top_corners[0].clockwise();
+        assert_eq!(Err(SolveError::Invalid(ValidationError::TwistedCorner)), solve(&mut cube));
+    }
 }
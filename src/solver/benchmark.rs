@@ -0,0 +1,185 @@
+//! Run solvers over the same scrambles and compare how they do.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+use rand::{SeedableRng, StdRng};
+use cube::Cube;
+use movement;
+use simplify::simplify;
+use super::Solver;
+
+/// How many random turns make a scramble.
+pub const SCRAMBLE_LENGTH: usize = 25;
+
+/// `count` cubes scrambled by `movement::random_turns`.  The same seed
+/// always gives the same cubes.
+pub fn scrambles(count: usize, seed: usize) -> Vec<Cube> {
+    let mut rng: StdRng = SeedableRng::from_seed(&[seed][..]);
+    (0..count).map(|_| {
+        let mut cube = Cube::new();
+        cube.apply_all(&movement::random_turns(&mut rng, SCRAMBLE_LENGTH));
+        cube
+    }).collect()
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Summary<T> {
+    pub mean: T,
+    pub median: T,
+    pub max: T,
+}
+
+fn summarize_moves(moves: &[usize]) -> Summary<f64> {
+    if moves.is_empty() {
+        return Summary::default();
+    }
+    let mut sorted = moves.to_vec();
+    sorted.sort();
+    let middle = sorted.len() / 2;
+    let median = if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) as f64 / 2.0
+    } else {
+        sorted[middle] as f64
+    };
+    Summary {
+        mean: sorted.iter().sum::<usize>() as f64 / sorted.len() as f64,
+        median,
+        max: sorted[sorted.len() - 1] as f64,
+    }
+}
+
+fn summarize_times(times: &[Duration]) -> Summary<Duration> {
+    if times.is_empty() {
+        return Summary::default();
+    }
+    let mut sorted = times.to_vec();
+    sorted.sort();
+    let middle = sorted.len() / 2;
+    let median = if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2
+    } else {
+        sorted[middle]
+    };
+    Summary {
+        mean: sorted.iter().sum::<Duration>() / sorted.len() as u32,
+        median,
+        max: sorted[sorted.len() - 1],
+    }
+}
+
+/// How a solver did over a set of scrambles.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Report {
+    pub name: String,
+    pub solved: usize,
+    /// Scrambles the solver gave up on or did not solve.
+    pub failed: usize,
+    pub moves: Summary<f64>,
+    pub time: Summary<Duration>,
+}
+
+/// Solve each scramble with the solver, checking every solution.  The
+/// statistics only cover the scrambles that were solved.  Moves are
+/// counted after `simplify`, so solvers that leave turns to cancel
+/// between their stages are compared fairly.
+pub fn benchmark(name: &str, solver: &dyn Solver, scrambles: &[Cube]) -> Report {
+    // Solvers build their tables when first used, which should not
+    // count against the first scramble.
+    let _ = solver.solve(&Cube::new());
+    let mut moves = Vec::new();
+    let mut times = Vec::new();
+    let mut failed = 0;
+    for scramble in scrambles {
+        let start = Instant::now();
+        let result = solver.solve(scramble);
+        let elapsed = start.elapsed();
+        match result {
+            Ok(movements) => {
+                let mut cube = scramble.clone();
+                cube.apply_all(&movements);
+                if cube.is_solved_in_any_orientation() {
+                    moves.push(simplify(&movements).len());
+                    times.push(elapsed);
+                } else {
+                    failed += 1;
+                }
+            },
+            Err(_) => failed += 1,
+        }
+    }
+    Report {
+        name: name.to_string(),
+        solved: moves.len(),
+        failed,
+        moves: summarize_moves(&moves),
+        time: summarize_times(&times),
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{:<16}{:>7}{:>7}{:>8.2}{:>7.1}{:>5}{:>13.3?}{:>13.3?}{:>13.3?}",
+               self.name, self.solved, self.failed,
+               self.moves.mean, self.moves.median, self.moves.max,
+               self.time.mean, self.time.median, self.time.max)
+    }
+}
+
+/// The header lines for a table of `Report`s.
+pub fn header() -> String {
+    format!("{:<30}{:^20}{:^39}\n{:<16}{:>7}{:>7}{:>8}{:>7}{:>5}{:>13}{:>13}{:>13}",
+            "", "moves", "time",
+            "solver", "solved", "failed", "mean", "median", "max", "mean", "median", "max")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use movement::Movement;
+    use parser::parse;
+    use solver::{Beginner, SolveError};
+    use solver::thistlethwaite::Thistlethwaite;
+
+    /// Solves `R U` with turns that mostly cancel.
+    struct Wasteful;
+
+    impl Solver for Wasteful {
+        fn solve(&self, _: &Cube) -> Result<Vec<Movement>, SolveError> {
+            Ok(parse("U' F F' R' L2 L2").unwrap())
+        }
+    }
+
+    #[test]
+    fn test_scrambles() {
+        assert_eq!(scrambles(3, 7), scrambles(3, 7));
+        assert!(scrambles(3, 7) != scrambles(3, 8));
+        assert_eq!(3, scrambles(3, 7).len());
+    }
+
+    #[test]
+    fn test_summarize() {
+        assert_eq!(Summary { mean: 2.5, median: 2.5, max: 4.0 }, summarize_moves(&[4, 1, 3, 2]));
+        assert_eq!(Summary { mean: 3.0, median: 2.0, max: 6.0 }, summarize_moves(&[6, 1, 2]));
+        let times = [Duration::from_millis(3), Duration::from_millis(1)];
+        assert_eq!(Duration::from_millis(2), summarize_times(&times).median);
+    }
+
+    #[test]
+    fn test_benchmark() {
+        let cubes = scrambles(4, 1);
+        let report = benchmark("beginner", &Beginner::new(), &cubes);
+        assert_eq!(4, report.solved);
+        assert_eq!(0, report.failed);
+        assert!(report.moves.mean > 20.0);
+
+        let report = benchmark("thistlethwaite", &Thistlethwaite::new(), &cubes);
+        assert_eq!(4, report.solved);
+        assert!(report.moves.max <= 52.0);
+
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("R U").unwrap());
+        let report = benchmark("wasteful", &Wasteful, &[cube]);
+        assert_eq!(1, report.solved);
+        assert_eq!(2.0, report.moves.max);
+    }
+}
@@ -0,0 +1,320 @@
+//! Pruning tables saved on disk so they are only built once.
+//!
+//! Each table is a file named after it holding a header followed by
+//! the table's bytes.  The header is the magic bytes `RUBICKTB`, the
+//! format version, the length of the table and an FNV-1a checksum of
+//! it, with the numbers little endian.  A file that does not match is
+//! ignored and the table is built and saved again.
+
+use std::env;
+use std::error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::OnceLock;
+
+const MAGIC: &[u8; 8] = b"RUBICKTB";
+
+/// Changed whenever the file format or the contents of a table change.
+pub const FORMAT_VERSION: u32 = 1;
+
+const HEADER_LENGTH: usize = 8 + 4 + 8 + 8;
+
+#[derive(Debug)]
+pub enum CacheError {
+    Io(io::Error),
+    /// The file does not start with the magic bytes.
+    NotATable,
+    /// The file was written by another version.
+    WrongVersion(u32),
+    /// The header gives a different length than the table should have.
+    WrongLength { expected: usize, found: usize },
+    /// The file holds a different number of bytes than its header gives.
+    Truncated { expected: usize, found: usize },
+    /// The table does not match its checksum.
+    BadChecksum,
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CacheError::Io(ref error) => write!(fmt, "{}", error),
+            CacheError::NotATable => write!(fmt, "not a table file"),
+            CacheError::WrongVersion(version) =>
+                write!(fmt, "format version {} instead of {}", version, FORMAT_VERSION),
+            CacheError::WrongLength { expected, found } =>
+                write!(fmt, "expected {} bytes but found {}", expected, found),
+            CacheError::Truncated { expected, found } =>
+                write!(fmt, "the header gives {} bytes but there are {}", expected, found),
+            CacheError::BadChecksum => write!(fmt, "the checksum does not match"),
+        }
+    }
+}
+
+impl error::Error for CacheError {}
+
+impl From<io::Error> for CacheError {
+    fn from(error: io::Error) -> Self {
+        CacheError::Io(error)
+    }
+}
+
+/// The 64 bit FNV-1a hash of the data.
+pub fn checksum(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    let mut b = [0; 4];
+    b.copy_from_slice(&bytes[..4]);
+    u32::from_le_bytes(b)
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut b = [0; 8];
+    b.copy_from_slice(&bytes[..8]);
+    u64::from_le_bytes(b)
+}
+
+/// A directory of saved tables.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct TableCache {
+    dir: Option<PathBuf>,
+    rebuild: bool,
+}
+
+impl TableCache {
+    /// Save tables in `dir`, which is made when first needed.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        TableCache { dir: Some(dir.into()), rebuild: false }
+    }
+
+    /// Build every table without saving it.
+    pub fn disabled() -> Self {
+        TableCache { dir: None, rebuild: false }
+    }
+
+    /// The directory named by `RUBICK_TABLES`, or else `rubick` in the
+    /// user's cache directory.
+    pub fn standard() -> Self {
+        if let Some(dir) = env::var_os("RUBICK_TABLES") {
+            return TableCache::new(dir);
+        }
+        if let Some(dir) = env::var_os("XDG_CACHE_HOME") {
+            return TableCache::new(Path::new(&dir).join("rubick"));
+        }
+        match env::var_os("HOME") {
+            Some(home) => TableCache::new(Path::new(&home).join(".cache").join("rubick")),
+            None => TableCache::disabled(),
+        }
+    }
+
+    /// Build and save tables even when they are already saved.
+    pub fn rebuilding(mut self) -> Self {
+        self.rebuild = true;
+        self
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    fn path(&self, name: &str) -> Option<PathBuf> {
+        self.dir.as_ref().map(|dir| dir.join(format!("{}.tbl", name)))
+    }
+
+    /// Read a saved table of `length` bytes.
+    pub fn load(&self, name: &str, length: usize) -> Result<Vec<u8>, CacheError> {
+        let path = match self.path(name) {
+            Some(path) => path,
+            None => return Err(CacheError::Io(io::Error::new(io::ErrorKind::NotFound,
+                                                             "no table directory"))),
+        };
+        let mut bytes = Vec::new();
+        fs::File::open(path)?.read_to_end(&mut bytes)?;
+        if bytes.len() < HEADER_LENGTH || &bytes[..8] != MAGIC {
+            return Err(CacheError::NotATable);
+        }
+        let version = read_u32(&bytes[8..]);
+        if version != FORMAT_VERSION {
+            return Err(CacheError::WrongVersion(version));
+        }
+        let found = read_u64(&bytes[12..]) as usize;
+        if found != length {
+            return Err(CacheError::WrongLength { expected: length, found });
+        }
+        let data = bytes.split_off(HEADER_LENGTH);
+        if data.len() != length {
+            return Err(CacheError::Truncated { expected: length, found: data.len() });
+        }
+        if read_u64(&bytes[20..]) != checksum(&data) {
+            return Err(CacheError::BadChecksum);
+        }
+        Ok(data)
+    }
+
+    /// Save a table.  It is written to a temporary file first so a
+    /// half written table is never left behind.
+    pub fn store(&self, name: &str, data: &[u8]) -> Result<(), CacheError> {
+        let path = match self.path(name) {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        fs::create_dir_all(self.dir().unwrap())?;
+        let temporary = path.with_extension(format!("tbl.{}", process::id()));
+        {
+            let mut file = fs::File::create(&temporary)?;
+            file.write_all(MAGIC)?;
+            file.write_all(&FORMAT_VERSION.to_le_bytes())?;
+            file.write_all(&(data.len() as u64).to_le_bytes())?;
+            file.write_all(&checksum(data).to_le_bytes())?;
+            file.write_all(data)?;
+            file.sync_all()?;
+        }
+        fs::rename(&temporary, &path)?;
+        Ok(())
+    }
+
+    /// Load the table if it is saved and sound, or else build it with
+    /// `build` and save it.  Problems with the files are reported on
+    /// standard error but never stop the table from being built.
+    pub fn get_or_build(&self, name: &str, length: usize, build: &dyn Fn() -> Vec<u8>)
+                        -> Vec<u8> {
+        if self.dir.is_none() {
+            return build();
+        }
+        if !self.rebuild {
+            match self.load(name, length) {
+                Ok(data) => return data,
+                Err(CacheError::Io(ref error)) if error.kind() == io::ErrorKind::NotFound => {},
+                Err(error) => eprintln!("rubick: rebuilding table {}: {}", name, error),
+            }
+        }
+        let data = build();
+        if let Err(error) = self.store(name, &data) {
+            eprintln!("rubick: could not save table {}: {}", name, error);
+        }
+        data
+    }
+}
+
+/// Use `cache` for every table built from now on.  This fails if a
+/// cache is already in use.
+pub fn install(cache: TableCache) -> Result<(), TableCache> {
+    INSTALLED.set(cache)
+}
+
+/// The cache given to `install`.  Without one, tables are built every
+/// time the program runs.
+pub fn installed() -> &'static TableCache {
+    INSTALLED.get_or_init(TableCache::disabled)
+}
+
+static INSTALLED: OnceLock<TableCache> = OnceLock::new();
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+
+    fn temporary_cache(name: &str) -> TableCache {
+        let dir = env::temp_dir().join(format!("rubick-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        TableCache::new(dir)
+    }
+
+    #[test]
+    fn test_checksum() {
+        assert_eq!(0xcbf29ce484222325, checksum(&[]));
+        assert_eq!(0xaf63dc4c8601ec8c, checksum(b"a"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let cache = temporary_cache("round-trip");
+        cache.store("table", &[1, 2, 3]).unwrap();
+        assert_eq!(vec![1, 2, 3], cache.load("table", 3).unwrap());
+        match cache.load("table", 4) {
+            Err(CacheError::WrongLength { expected: 4, found: 3 }) => {},
+            result => panic!("{:?}", result),
+        }
+
+        // A header claiming 5 bytes for a table of 3.
+        let path = cache.path("table").unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[12] = 5;
+        fs::write(&path, &bytes).unwrap();
+        match cache.load("table", 3) {
+            Err(CacheError::WrongLength { expected: 3, found: 5 }) => {},
+            result => panic!("{:?}", result),
+        }
+        match cache.load("table", 5) {
+            Err(CacheError::Truncated { expected: 5, found: 3 }) => {},
+            result => panic!("{:?}", result),
+        }
+        fs::remove_dir_all(cache.dir().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_get_or_build() {
+        let cache = temporary_cache("get-or-build");
+        let builds = Cell::new(0);
+        let build = || { builds.set(builds.get() + 1); vec![7; 100] };
+        assert_eq!(vec![7; 100], cache.get_or_build("table", 100, &build));
+        assert_eq!(vec![7; 100], cache.get_or_build("table", 100, &build));
+        assert_eq!(1, builds.get());
+
+        // A corrupt table is built again and replaced.
+        let path = cache.path("table").unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[HEADER_LENGTH + 5] = 8;
+        fs::write(&path, &bytes).unwrap();
+        match cache.load("table", 100) {
+            Err(CacheError::BadChecksum) => {},
+            result => panic!("{:?}", result),
+        }
+        assert_eq!(vec![7; 100], cache.get_or_build("table", 100, &build));
+        assert_eq!(2, builds.get());
+        assert_eq!(vec![7; 100], cache.load("table", 100).unwrap());
+
+        assert_eq!(vec![7; 100], cache.clone().rebuilding().get_or_build("table", 100, &build));
+        assert_eq!(3, builds.get());
+        fs::remove_dir_all(cache.dir().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_bad_files() {
+        let cache = temporary_cache("bad-files");
+        cache.store("table", &[1, 2, 3]).unwrap();
+        let path = cache.path("table").unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+
+        bytes[8] = FORMAT_VERSION as u8 + 1;
+        fs::write(&path, &bytes).unwrap();
+        match cache.load("table", 3) {
+            Err(CacheError::WrongVersion(_)) => {},
+            result => panic!("{:?}", result),
+        }
+
+        fs::write(&path, b"not a table").unwrap();
+        match cache.load("table", 3) {
+            Err(CacheError::NotATable) => {},
+            result => panic!("{:?}", result),
+        }
+        fs::remove_dir_all(cache.dir().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_disabled() {
+        let cache = TableCache::disabled();
+        let builds = Cell::new(0);
+        let build = || { builds.set(builds.get() + 1); vec![1] };
+        cache.get_or_build("table", 1, &build);
+        cache.get_or_build("table", 1, &build);
+        assert_eq!(2, builds.get());
+    }
+}
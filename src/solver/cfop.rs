@@ -0,0 +1,446 @@
+//! A solve in the style of CFOP, the method most speedcubers use: the
+//! bottom cross, the four pairs of the first two layers, then the top
+//! layer with one OLL and one PLL algorithm.  The solutions are longer
+//! than those of the computer methods but are the ones a person could
+//! follow.
+
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+use cube::Cube;
+use cubies::{self, Cubies};
+use movement::{Direction, Movement, Times, FACE_TURNS};
+use predicates::{self, OllCase, PllCase, F2L_SLOTS};
+use simplify::simplify;
+use super::{piece_distances, redundant, side_turns, SolveError, Solver};
+
+/// The names of the pair slots, in the order of `F2L_SLOTS`.
+pub const SLOT_NAMES: [&str; 4] = ["front left", "front right", "back right", "back left"];
+
+/// The faces next to each pair slot, numbered as in `FACE_TURNS`.
+const SLOT_FACES: [[usize; 2]; 4] = [[2, 4], [1, 2], [1, 5], [4, 5]];
+
+/// The most turns tried for a pair before every side face is allowed.
+const MAX_PAIR_DEPTH: usize = 12;
+
+/// Where a corner is and how it is twisted, as `slot * 3 + twist`,
+/// after each of `FACE_TURNS`.
+fn corner_turns() -> Vec<[u8; 18]> {
+    let mut table = vec![[0; 18]; 24];
+    for (m, turn) in cubies::face_turns().iter().enumerate() {
+        for slot in 0..8 {
+            let from = turn.corner_permutation[slot] as usize;
+            for twist in 0..3 {
+                let to = slot * 3 + (twist + turn.corner_orientation[slot] as usize) % 3;
+                table[from * 3 + twist][m] = to as u8;
+            }
+        }
+    }
+    table
+}
+
+fn cross_index(states: &[u8; 4]) -> usize {
+    states.iter().rev().fold(0, |index, &state| index * 24 + state as usize)
+}
+
+struct Tables {
+    corner_turns: Vec<[u8; 18]>,
+    side_turns: Vec<[u8; 18]>,
+    /// How many turns each state of the corners is from each slot.
+    corner_distances: Vec<[u8; 24]>,
+    side_distances: Vec<[u8; 24]>,
+    /// How many turns the bottom sides are from their places, by
+    /// `cross_index`.
+    cross: Vec<u8>,
+    /// How many turns the pieces of each pair are from their places, by
+    /// `corner * 24 + side`.
+    pairs: Vec<Vec<u8>>,
+}
+
+impl Tables {
+    fn new() -> Self {
+        let corner_turns = corner_turns();
+        let side_turns = side_turns();
+        let corner_distances = (0..8).map(|c| piece_distances(&corner_turns, c * 3)).collect();
+        let side_distances = (0..12).map(|s| piece_distances(&side_turns, s * 2)).collect();
+
+        let mut cross = vec![u8::MAX; 24 * 24 * 24 * 24];
+        let home = [16, 18, 20, 22];
+        cross[cross_index(&home)] = 0;
+        let mut queue = VecDeque::new();
+        queue.push_back(home);
+        while let Some(states) = queue.pop_front() {
+            let distance = cross[cross_index(&states)];
+            for next in (0..18).map(|m| states.map(|s| side_turns[s as usize][m])) {
+                if cross[cross_index(&next)] == u8::MAX {
+                    cross[cross_index(&next)] = distance + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let pairs = F2L_SLOTS.iter().map(|&(corner, side)| {
+            let mut pair = vec![u8::MAX; 24 * 24];
+            let home = (corner * 3, side * 2);
+            pair[home.0 * 24 + home.1] = 0;
+            let mut queue = VecDeque::new();
+            queue.push_back(home);
+            while let Some((c, s)) = queue.pop_front() {
+                for m in 0..18 {
+                    let next = (corner_turns[c][m] as usize, side_turns[s][m] as usize);
+                    if pair[next.0 * 24 + next.1] == u8::MAX {
+                        pair[next.0 * 24 + next.1] = pair[c * 24 + s] + 1;
+                        queue.push_back(next);
+                    }
+                }
+            }
+            pair
+        }).collect();
+
+        Tables { corner_turns, side_turns, corner_distances, side_distances, cross, pairs }
+    }
+
+    fn get() -> &'static Tables {
+        static TABLES: OnceLock<Tables> = OnceLock::new();
+        TABLES.get_or_init(Tables::new)
+    }
+}
+
+fn corner_state(cubies: &Cubies, corner: usize) -> u8 {
+    let slot = cubies.corner_permutation.iter().position(|&p| p as usize == corner).unwrap();
+    (slot * 3) as u8 + cubies.corner_orientation[slot]
+}
+
+fn side_state(cubies: &Cubies, side: usize) -> u8 {
+    let slot = cubies.side_permutation.iter().position(|&p| p as usize == side).unwrap();
+    (slot * 2) as u8 + cubies.side_orientation[slot]
+}
+
+/// Solve the bottom sides in the fewest turns.
+fn solve_cross(cubies: &Cubies) -> Vec<usize> {
+    let tables = Tables::get();
+    let mut states = [0; 4];
+    for (i, state) in states.iter_mut().enumerate() {
+        *state = side_state(cubies, 8 + i);
+    }
+    let mut path = Vec::new();
+    while tables.cross[cross_index(&states)] > 0 {
+        let distance = tables.cross[cross_index(&states)];
+        let turn = |states: [u8; 4], m: usize| states.map(|s| tables.side_turns[s as usize][m]);
+        let m = (0..18).find(|&m| tables.cross[cross_index(&turn(states, m))] < distance).unwrap();
+        states = turn(states, m);
+        path.push(m);
+    }
+    path
+}
+
+/// A piece that has to end up in its place, as its state in
+/// `corner_turns` or `side_turns`.
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+    corner: bool,
+    home: usize,
+    state: u8,
+}
+
+/// Look for the turns that put one pair in its slot without disturbing
+/// the cross or the pairs already solved.
+struct PairSearch<'a> {
+    tables: &'a Tables,
+    slot: usize,
+    turns: Vec<usize>,
+}
+
+impl<'a> PairSearch<'a> {
+    fn estimate(&self, pair: (u8, u8), kept: &[Piece]) -> usize {
+        let pair = self.tables.pairs[self.slot][pair.0 as usize * 24 + pair.1 as usize];
+        kept.iter().map(|piece| if piece.corner {
+            self.tables.corner_distances[piece.home][piece.state as usize]
+        } else {
+            self.tables.side_distances[piece.home][piece.state as usize]
+        }).fold(pair, u8::max) as usize
+    }
+
+    fn search(&self, pair: (u8, u8), kept: &[Piece], depth: usize,
+              path: &mut Vec<usize>) -> bool {
+        let estimate = self.estimate(pair, kept);
+        if estimate == 0 {
+            return true;
+        }
+        if estimate > depth {
+            return false;
+        }
+        let tables = self.tables;
+        for &m in &self.turns {
+            if redundant(path.last().cloned(), m) {
+                continue;
+            }
+            let next_pair = (tables.corner_turns[pair.0 as usize][m],
+                             tables.side_turns[pair.1 as usize][m]);
+            let next_kept: Vec<Piece> = kept.iter().map(|piece| Piece {
+                state: if piece.corner {
+                    tables.corner_turns[piece.state as usize][m]
+                } else {
+                    tables.side_turns[piece.state as usize][m]
+                },
+                ..*piece
+            }).collect();
+            path.push(m);
+            if self.search(next_pair, &next_kept, depth - 1, path) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+}
+
+/// The slot of the first two layers a corner or middle side slot
+/// belongs to.
+fn pair_slot(corner: bool, slot: usize) -> Option<usize> {
+    F2L_SLOTS.iter().position(|&(c, s)| if corner { c == slot } else { s == slot })
+}
+
+fn face_turns_of(faces: &[usize]) -> Vec<usize> {
+    let mut turns: Vec<usize> = faces.iter().flat_map(|&f| f * 3..f * 3 + 3).collect();
+    turns.sort();
+    turns.dedup();
+    turns
+}
+
+/// Solve the pair for `slot`, keeping the cross and the pairs in
+/// `solved` in place.  Only the top and the faces around the slot, or
+/// around the slots the pieces are stuck in, are turned.
+fn solve_pair(cubies: &Cubies, slot: usize, solved: &[usize]) -> Vec<usize> {
+    let tables = Tables::get();
+    let (corner, side) = F2L_SLOTS[slot];
+    let pair = (corner_state(cubies, corner), side_state(cubies, side));
+    let mut kept: Vec<Piece> = (8..12)
+        .map(|home| Piece { corner: false, home, state: side_state(cubies, home) })
+        .collect();
+    for &other in solved {
+        let (corner, side) = F2L_SLOTS[other];
+        kept.push(Piece { corner: true, home: corner, state: corner_state(cubies, corner) });
+        kept.push(Piece { corner: false, home: side, state: side_state(cubies, side) });
+    }
+
+    let mut faces = vec![0];
+    faces.extend_from_slice(&SLOT_FACES[slot]);
+    let stuck = [pair_slot(true, pair.0 as usize / 3), pair_slot(false, pair.1 as usize / 2)];
+    for &stuck in stuck.iter().flatten() {
+        faces.extend_from_slice(&SLOT_FACES[stuck]);
+    }
+    let mut search = PairSearch { tables, slot, turns: face_turns_of(&faces) };
+    let mut path = Vec::new();
+    if (0..MAX_PAIR_DEPTH + 1).any(|depth| search.search(pair, &kept, depth, &mut path)) {
+        return path;
+    }
+    search.turns = face_turns_of(&[0, 1, 2, 4, 5]);
+    let mut depth = MAX_PAIR_DEPTH;
+    while !search.search(pair, &kept, depth, &mut path) {
+        depth += 1;
+    }
+    path
+}
+
+fn top_turn(quarter_turns: u8) -> Option<Movement> {
+    Times::from_quarter_turns(quarter_turns).map(|t| Movement::new(Direction::Top, t))
+}
+
+/// The turns of each stage of the solve.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct CfopSolution {
+    pub cross: Vec<Movement>,
+    /// The slot of each pair, numbered as in `SLOT_NAMES`, and the
+    /// turns that solve it, in the order they were solved.  Pairs that
+    /// were already solved are left out.
+    pub pairs: Vec<(usize, Vec<Movement>)>,
+    pub oll_case: Option<OllCase>,
+    pub oll: Vec<Movement>,
+    pub pll_case: Option<PllCase>,
+    /// The PLL algorithm with the turns of the top around it, or just a
+    /// turn of the top if that is all that was left.
+    pub pll: Vec<Movement>,
+}
+
+impl CfopSolution {
+    /// Every turn, in order.
+    pub fn movements(&self) -> Vec<Movement> {
+        self.stages().into_iter().flat_map(|(_, movements)| movements.iter().cloned()).collect()
+    }
+
+    /// The name and turns of each stage, such as `("OLL 27", ...)`.
+    pub fn stages(&self) -> Vec<(String, &[Movement])> {
+        let mut stages = vec![("cross".to_string(), &self.cross[..])];
+        for &(slot, ref movements) in &self.pairs {
+            stages.push((format!("F2L {}", SLOT_NAMES[slot]), &movements[..]));
+        }
+        stages.push((match self.oll_case {
+            Some(case) => format!("OLL {}", case.number),
+            None => "OLL skip".to_string(),
+        }, &self.oll[..]));
+        stages.push((match self.pll_case {
+            Some(case) => format!("PLL {}", case.name),
+            None => "PLL skip".to_string(),
+        }, &self.pll[..]));
+        stages
+    }
+
+    pub fn len(&self) -> usize {
+        self.stages().iter().map(|&(_, movements)| movements.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Solves cubes as a person would with CFOP.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Cfop;
+
+impl Cfop {
+    pub fn new() -> Self {
+        Cfop
+    }
+
+    /// Solve the cube relative to its centers, with the cross on the
+    /// bottom.  The easiest pair left is solved each time.
+    pub fn solve_stages(&self, cube: &Cube) -> Result<CfopSolution, SolveError> {
+        cube.validate()?;
+        let mut cube = cube.clone();
+        let mut solution = CfopSolution::default();
+        let cubies = Cubies::from_cube(&cube)?;
+        solution.cross = solve_cross(&cubies).into_iter().map(|m| FACE_TURNS[m]).collect();
+        cube.apply_all(&solution.cross);
+
+        loop {
+            let cubies = Cubies::from_cube(&cube)?;
+            let pairs = predicates::f2l_pairs_solved(&cube);
+            let solved: Vec<usize> = (0..4).filter(|&s| pairs[s]).collect();
+            let best = (0..4).filter(|&s| !pairs[s])
+                .map(|slot| (slot, solve_pair(&cubies, slot, &solved)))
+                .min_by_key(|(_, path)| path.len());
+            match best {
+                Some((slot, path)) => {
+                    let movements: Vec<Movement> = path.iter().map(|&m| FACE_TURNS[m]).collect();
+                    cube.apply_all(&movements);
+                    solution.pairs.push((slot, movements));
+                },
+                None => break,
+            }
+        }
+        if !predicates::middle_solved(&cube) {
+            return Err(SolveError::StageFailed("first two layers"));
+        }
+
+        solution.oll_case = predicates::oll_case(&cube);
+        if let Some(case) = solution.oll_case {
+            solution.oll.extend(case.before);
+            solution.oll.extend(case.algorithm());
+            cube.apply_all(&solution.oll);
+        }
+        if !predicates::oll_solved(&cube) {
+            return Err(SolveError::StageFailed("OLL"));
+        }
+
+        solution.pll_case = predicates::pll_case(&cube);
+        if let Some(case) = solution.pll_case {
+            solution.pll.extend(case.before);
+            solution.pll.extend(case.algorithm());
+            solution.pll.extend(case.after);
+            cube.apply_all(&solution.pll);
+        }
+        let turns = (0..4).find(|&turns| {
+            let mut cube = cube.clone();
+            cube.apply_all(&top_turn(turns).into_iter().collect::<Vec<_>>());
+            cube.is_solved_in_any_orientation()
+        }).ok_or(SolveError::StageFailed("PLL"))?;
+        solution.pll.extend(top_turn(turns));
+        Ok(solution)
+    }
+}
+
+impl Solver for Cfop {
+    /// Solve the stages and join them, cancelling turns where they meet.
+    fn solve(&self, cube: &Cube) -> Result<Vec<Movement>, SolveError> {
+        self.solve_stages(cube).map(|solution| simplify(&solution.movements()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parser::parse;
+    use predicates::*;
+    use solver::benchmark::scrambles;
+    use validation::ValidationError;
+
+    #[test]
+    fn test_solve_cross() {
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("D R' F2").unwrap());
+        let path = solve_cross(&Cubies::from_cube(&cube).unwrap());
+        assert_eq!(3, path.len());
+        cube.apply_all(&path.iter().map(|&m| FACE_TURNS[m]).collect::<Vec<_>>());
+        assert!(bottom_cross_solved(&cube));
+    }
+
+    #[test]
+    fn test_solve_pair() {
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("R U R'").unwrap());
+        let path = solve_pair(&Cubies::from_cube(&cube).unwrap(), 1, &[0, 2, 3]);
+        assert_eq!(3, path.len());
+        cube.apply_all(&path.iter().map(|&m| FACE_TURNS[m]).collect::<Vec<_>>());
+        assert!(cube.is_solved());
+    }
+
+    #[test]
+    fn test_solve() {
+        for start in scrambles(3, 2) {
+            let solution = Cfop::new().solve_stages(&start).unwrap();
+            let mut cube = start.clone();
+            cube.apply_all(&solution.cross);
+            assert!(bottom_cross_solved(&cube));
+            for &(slot, ref movements) in &solution.pairs {
+                cube.apply_all(movements);
+                assert!(f2l_pairs_solved(&cube)[slot]);
+                assert!(bottom_cross_solved(&cube));
+            }
+            assert!(middle_solved(&cube));
+            cube.apply_all(&solution.oll);
+            assert!(oll_solved(&cube));
+            cube.apply_all(&solution.pll);
+            assert!(cube.is_solved());
+
+            assert_eq!(solution.pairs.len() + 3, solution.stages().len());
+            let movements = Cfop::new().solve(&start).unwrap();
+            assert!(movements.len() <= solution.len());
+            assert_eq!(simplify(&movements), movements);
+            let mut cube = start.clone();
+            cube.apply_all(&movements);
+            assert!(cube.is_solved());
+        }
+    }
+
+    #[test]
+    fn test_solve_last_layer() {
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("R U R' U R U2 R' U").unwrap());
+        let solution = Cfop::new().solve_stages(&cube).unwrap();
+        assert!(solution.cross.is_empty() && solution.pairs.is_empty());
+        assert_eq!(Some(26), solution.oll_case.map(|case| case.number));
+        cube.apply_all(&solution.movements());
+        assert!(cube.is_solved());
+
+        assert!(Cfop::new().solve_stages(&Cube::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_solve_invalid() {
+        let mut cube = Cube::new();
+        cube.top_sides[0].flip();
+        assert_eq!(Err(SolveError::Invalid(ValidationError::FlippedSide)),
+                   Cfop::new().solve(&cube));
+    }
+}
@@ -0,0 +1,441 @@
+//! Kociemba's two phase algorithm.  The first phase brings the cube
+//! into the group `<U, D, R2, L2, F2, B2>`, where every corner and side
+//! is oriented and the middle sides are in the middle layer.  The
+//! second phase solves the cube using only those turns.
+
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use coordinates::{orientation_index, orientation_from_index, permutation_index,
+                  permutation_from_index, combination_index, combination_from_index,
+                  CORNER_ORIENTATIONS, SIDE_ORIENTATIONS, CORNER_PERMUTATIONS};
+use cube::Cube;
+use cubies::{self, Cubies};
+use movement::{Movement, FACE_TURNS};
+use super::{cache, redundant, SolveError, Solver};
+
+/// The ways to choose the four slots holding the middle sides.
+pub const SLICES: usize = 495;
+/// The permutations of the eight top and bottom sides.
+pub const LAYER_SIDE_PERMUTATIONS: usize = 40320;
+/// The permutations of the four middle sides.
+pub const SLICE_PERMUTATIONS: usize = 24;
+
+/// The indices in `FACE_TURNS` of the turns allowed in the second
+/// phase.
+pub const PHASE_TWO_TURNS: [usize; 10] = [0, 1, 2, 4, 7, 9, 10, 11, 13, 16];
+
+/// The longest a phase two solution ever needs to be.
+const MAX_PHASE_TWO: usize = 18;
+
+/// How many nodes to search between looking at the clock.
+const CLOCK_INTERVAL: usize = 1 << 12;
+
+fn is_slice_side(piece: u8) -> bool {
+    (4..8).contains(&piece)
+}
+
+/// Encode which slots hold the middle sides.
+pub fn slice_index(side_permutation: &[u8; 12]) -> usize {
+    let mut chosen = [false; 12];
+    for (c, &piece) in chosen.iter_mut().zip(side_permutation.iter()) {
+        *c = is_slice_side(piece);
+    }
+    combination_index(&chosen)
+}
+
+/// Decode a `slice_index` into a side permutation with the middle
+/// sides in those slots.
+pub fn slice_from_index(index: usize) -> [u8; 12] {
+    let mut slice_slots = [false; 12];
+    combination_from_index(index, 4, &mut slice_slots);
+    let mut permutation = [0; 12];
+    let (mut slice, mut other) = (4..8, (0..4).chain(8..12));
+    for (slot, &in_slice) in slice_slots.iter().enumerate() {
+        permutation[slot] = if in_slice { slice.next() } else { other.next() }.unwrap();
+    }
+    permutation
+}
+
+/// The pieces in the top and bottom side slots, numbered `0..8`.
+fn layer_sides(side_permutation: &[u8; 12]) -> [u8; 8] {
+    let mut sides = [0; 8];
+    for (i, &slot) in [0, 1, 2, 3, 8, 9, 10, 11].iter().enumerate() {
+        let piece = side_permutation[slot];
+        sides[i] = if piece < 4 { piece } else { piece - 4 };
+    }
+    sides
+}
+
+fn slice_sides(side_permutation: &[u8; 12]) -> [u8; 4] {
+    let mut sides = [0; 4];
+    for (i, side) in sides.iter_mut().enumerate() {
+        *side = side_permutation[4 + i] - 4;
+    }
+    sides
+}
+
+/// A table of where each coordinate goes after each turn.
+fn move_table(size: usize, turns: &[usize], coordinate: &dyn Fn(usize, &Cubies) -> usize)
+              -> Vec<Vec<u16>> {
+    let face_turns = cubies::face_turns();
+    (0..size).map(|index| {
+        turns.iter().map(|&turn| coordinate(index, &face_turns[turn]) as u16).collect()
+    }).collect()
+}
+
+/// The fewest turns from each pair of coordinates to the goal, found
+/// by searching outwards from it, or loaded from the installed cache.
+/// The moves of the two coordinates must be given for the same turns.
+fn pruning_table(name: &str, first: &[Vec<u16>], second: &[Vec<u16>], goal: usize) -> Vec<u8> {
+    cache::installed().get_or_build(name, first.len() * second.len(), &|| {
+        build_pruning_table(first, second, goal)
+    })
+}
+
+fn build_pruning_table(first: &[Vec<u16>], second: &[Vec<u16>], goal: usize) -> Vec<u8> {
+    let size = second.len();
+    let mut distances = vec![u8::MAX; first.len() * size];
+    distances[goal] = 0;
+    let mut queue = VecDeque::new();
+    queue.push_back(goal);
+    while let Some(index) = queue.pop_front() {
+        let (a, b) = (index / size, index % size);
+        for (&next_a, &next_b) in first[a].iter().zip(second[b].iter()) {
+            let next = next_a as usize * size + next_b as usize;
+            if distances[next] == u8::MAX {
+                distances[next] = distances[index] + 1;
+                queue.push_back(next);
+            }
+        }
+    }
+    distances
+}
+
+/// The move and pruning tables used by the search.
+pub struct Tables {
+    corner_orientation: Vec<Vec<u16>>,
+    side_orientation: Vec<Vec<u16>>,
+    slice: Vec<Vec<u16>>,
+    corner_permutation: Vec<Vec<u16>>,
+    layer_side_permutation: Vec<Vec<u16>>,
+    slice_permutation: Vec<Vec<u16>>,
+    /// Indexed by corner orientation and then slice.
+    corner_orientation_slice: Vec<u8>,
+    /// Indexed by side orientation and then slice.
+    side_orientation_slice: Vec<u8>,
+    /// Indexed by corner permutation and then slice permutation.
+    corner_slice_permutation: Vec<u8>,
+    /// Indexed by layer side permutation and then slice permutation.
+    side_slice_permutation: Vec<u8>,
+}
+
+impl Tables {
+    pub fn new() -> Self {
+        let all: Vec<usize> = (0..18).collect();
+        let corner_orientation = move_table(CORNER_ORIENTATIONS, &all, &|index, turn| {
+            let mut cubies = Cubies::new();
+            orientation_from_index(index, 3, &mut cubies.corner_orientation);
+            orientation_index(&cubies.multiply(turn).corner_orientation, 3)
+        });
+        let side_orientation = move_table(SIDE_ORIENTATIONS, &all, &|index, turn| {
+            let mut cubies = Cubies::new();
+            orientation_from_index(index, 2, &mut cubies.side_orientation);
+            orientation_index(&cubies.multiply(turn).side_orientation, 2)
+        });
+        let slice = move_table(SLICES, &all, &|index, turn| {
+            let mut cubies = Cubies::new();
+            cubies.side_permutation = slice_from_index(index);
+            slice_index(&cubies.multiply(turn).side_permutation)
+        });
+        let corner_permutation = move_table(CORNER_PERMUTATIONS, &PHASE_TWO_TURNS,
+                                            &|index, turn| {
+            let mut cubies = Cubies::new();
+            permutation_from_index(index, &mut cubies.corner_permutation);
+            permutation_index(&cubies.multiply(turn).corner_permutation)
+        });
+        let layer_side_permutation = move_table(LAYER_SIDE_PERMUTATIONS, &PHASE_TWO_TURNS,
+                                                &|index, turn| {
+            let mut sides = [0; 8];
+            permutation_from_index(index, &mut sides);
+            let mut cubies = Cubies::new();
+            for (i, &slot) in [0, 1, 2, 3, 8, 9, 10, 11].iter().enumerate() {
+                cubies.side_permutation[slot] = if sides[i] < 4 { sides[i] } else { sides[i] + 4 };
+            }
+            permutation_index(&layer_sides(&cubies.multiply(turn).side_permutation))
+        });
+        let slice_permutation = move_table(SLICE_PERMUTATIONS, &PHASE_TWO_TURNS,
+                                           &|index, turn| {
+            let mut sides = [0; 4];
+            permutation_from_index(index, &mut sides);
+            let mut cubies = Cubies::new();
+            for (i, &side) in sides.iter().enumerate() {
+                cubies.side_permutation[4 + i] = side + 4;
+            }
+            permutation_index(&slice_sides(&cubies.multiply(turn).side_permutation))
+        });
+        let solved_slice = slice_index(&Cubies::new().side_permutation);
+        Tables {
+            corner_orientation_slice: pruning_table("kociemba-corner-orientation-slice",
+                                                    &corner_orientation, &slice, solved_slice),
+            side_orientation_slice: pruning_table("kociemba-side-orientation-slice",
+                                                  &side_orientation, &slice, solved_slice),
+            corner_slice_permutation: pruning_table("kociemba-corner-slice-permutation",
+                                                    &corner_permutation, &slice_permutation, 0),
+            side_slice_permutation: pruning_table("kociemba-side-slice-permutation",
+                                                  &layer_side_permutation, &slice_permutation,
+                                                  0),
+            corner_orientation,
+            side_orientation,
+            slice,
+            corner_permutation,
+            layer_side_permutation,
+            slice_permutation,
+        }
+    }
+
+    /// The tables shared by every search, built the first time they
+    /// are needed.
+    pub fn get() -> &'static Tables {
+        static TABLES: OnceLock<Tables> = OnceLock::new();
+        TABLES.get_or_init(Tables::new)
+    }
+}
+
+impl Default for Tables {
+    fn default() -> Self {
+        Tables::new()
+    }
+}
+
+/// Solves cubes with Kociemba's two phase algorithm.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Kociemba {
+    /// Stop at the first solution at most this long.
+    pub max_length: usize,
+    /// Give up if no solution has been found after this long.
+    pub time_budget: Option<Duration>,
+    /// Give up if no solution has been found after searching this many
+    /// nodes.
+    pub node_budget: Option<usize>,
+}
+
+impl Kociemba {
+    pub fn new(max_length: usize, time_budget: Duration) -> Self {
+        Kociemba { max_length, time_budget: Some(time_budget), node_budget: None }
+    }
+
+    /// Search at most `node_budget` nodes, however long they take, so a
+    /// cube is always given the same solution or error.
+    pub fn with_node_budget(max_length: usize, node_budget: usize) -> Self {
+        Kociemba { max_length, time_budget: None, node_budget: Some(node_budget) }
+    }
+}
+
+impl Solver for Kociemba {
+    /// Find a solution of at most `max_length` face turns.
+    fn solve(&self, cube: &Cube) -> Result<Vec<Movement>, SolveError> {
+        let cubies = Cubies::from_cube(cube)?;
+        cubies.validate()?;
+        let tables = Tables::get();
+        let mut search = Search {
+            tables,
+            cubies: &cubies,
+            deadline: self.time_budget.map(|budget| Instant::now() + budget),
+            node_budget: self.node_budget.unwrap_or(usize::MAX),
+            nodes: 0,
+            path: Vec::new(),
+            timed_out: false,
+        };
+        let corner_orientation = orientation_index(&cubies.corner_orientation, 3);
+        let side_orientation = orientation_index(&cubies.side_orientation, 2);
+        let slice = slice_index(&cubies.side_permutation);
+        for depth in 0..self.max_length + 1 {
+            if search.phase_one(corner_orientation, side_orientation, slice,
+                                depth, self.max_length) {
+                return Ok(search.path.iter().map(|&m| FACE_TURNS[m]).collect());
+            }
+            if search.timed_out {
+                return Err(SolveError::Timeout);
+            }
+        }
+        Err(SolveError::TooLong)
+    }
+}
+
+impl Default for Kociemba {
+    /// Look for solutions of at most 21 moves for up to a second.
+    fn default() -> Self {
+        Kociemba::new(21, Duration::from_secs(1))
+    }
+}
+
+struct Search<'a> {
+    tables: &'a Tables,
+    cubies: &'a Cubies,
+    deadline: Option<Instant>,
+    node_budget: usize,
+    nodes: usize,
+    /// The indices in `FACE_TURNS` of the turns made so far.
+    path: Vec<usize>,
+    timed_out: bool,
+}
+
+impl<'a> Search<'a> {
+    fn out_of_time(&mut self) -> bool {
+        self.nodes += 1;
+        if self.nodes > self.node_budget {
+            self.timed_out = true;
+        } else if self.nodes.is_multiple_of(CLOCK_INTERVAL) {
+            if let Some(deadline) = self.deadline {
+                self.timed_out |= Instant::now() >= deadline;
+            }
+        }
+        self.timed_out
+    }
+
+    /// Search for phase one solutions of exactly `depth` turns, and
+    /// for each try to finish with at most `max_length` turns in all.
+    fn phase_one(&mut self, corner_orientation: usize, side_orientation: usize,
+                 slice: usize, depth: usize, max_length: usize) -> bool {
+        if self.out_of_time() {
+            return false;
+        }
+        let t = self.tables;
+        let estimate = t.corner_orientation_slice[corner_orientation * SLICES + slice]
+            .max(t.side_orientation_slice[side_orientation * SLICES + slice]) as usize;
+        if estimate > depth {
+            return false;
+        }
+        if depth == 0 {
+            // A solution ending in a phase two turn would have been
+            // found at a smaller depth.
+            if self.path.last().is_some_and(|m| PHASE_TWO_TURNS.contains(m)) {
+                return false;
+            }
+            return self.start_phase_two(max_length - self.path.len());
+        }
+        for m in 0..18 {
+            if redundant(self.path.last().cloned(), m) {
+                continue;
+            }
+            self.path.push(m);
+            if self.phase_one(t.corner_orientation[corner_orientation][m] as usize,
+                              t.side_orientation[side_orientation][m] as usize,
+                              t.slice[slice][m] as usize,
+                              depth - 1, max_length) {
+                return true;
+            }
+            self.path.pop();
+        }
+        false
+    }
+
+    fn start_phase_two(&mut self, max_depth: usize) -> bool {
+        let mut cubies = self.cubies.clone();
+        for &m in &self.path {
+            cubies = cubies.multiply(&cubies::face_turns()[m]);
+        }
+        let corner_permutation = permutation_index(&cubies.corner_permutation);
+        let layer_side_permutation = permutation_index(&layer_sides(&cubies.side_permutation));
+        let slice_permutation = permutation_index(&slice_sides(&cubies.side_permutation));
+        for depth in 0..max_depth.min(MAX_PHASE_TWO) + 1 {
+            if self.phase_two(corner_permutation, layer_side_permutation, slice_permutation,
+                              depth) {
+                return true;
+            }
+            if self.timed_out {
+                return false;
+            }
+        }
+        false
+    }
+
+    fn phase_two(&mut self, corner_permutation: usize, layer_side_permutation: usize,
+                 slice_permutation: usize, depth: usize) -> bool {
+        if self.out_of_time() {
+            return false;
+        }
+        let t = self.tables;
+        let estimate = t.corner_slice_permutation
+            [corner_permutation * SLICE_PERMUTATIONS + slice_permutation]
+            .max(t.side_slice_permutation
+                 [layer_side_permutation * SLICE_PERMUTATIONS + slice_permutation]) as usize;
+        if estimate > depth {
+            return false;
+        }
+        if depth == 0 {
+            return true;
+        }
+        for (i, &m) in PHASE_TWO_TURNS.iter().enumerate() {
+            if redundant(self.path.last().cloned(), m) {
+                continue;
+            }
+            self.path.push(m);
+            if self.phase_two(t.corner_permutation[corner_permutation][i] as usize,
+                              t.layer_side_permutation[layer_side_permutation][i] as usize,
+                              t.slice_permutation[slice_permutation][i] as usize,
+                              depth - 1) {
+                return true;
+            }
+            self.path.pop();
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parser::parse;
+    use rand::{Rng, SeedableRng, StdRng};
+    use validation::ValidationError;
+
+    #[test]
+    fn test_slice_index() {
+        for index in 0..SLICES {
+            assert_eq!(index, slice_index(&slice_from_index(index)));
+        }
+        assert_eq!(SLICES - 1, slice_index(&[0, 1, 2, 3, 8, 9, 10, 11, 4, 5, 6, 7]));
+    }
+
+    #[test]
+    fn test_solve() {
+        let kociemba = Kociemba::default();
+        let mut rng: StdRng = SeedableRng::from_seed(&[13][..]);
+        for _ in 0..3 {
+            let mut cube = Cube::new();
+            for _ in 0..30 {
+                cube.apply(&FACE_TURNS[rng.gen_range(0, 18)]);
+            }
+            let solution = kociemba.solve(&cube).unwrap();
+            assert!(solution.len() <= 21);
+            cube.apply_all(&solution);
+            assert!(cube.is_solved());
+        }
+    }
+
+    #[test]
+    fn test_solve_short() {
+        assert_eq!(Ok(vec![]), Kociemba::default().solve(&Cube::new()));
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("R U' F2 D").unwrap());
+        assert_eq!(Ok(parse("D' F2 U R'").unwrap()),
+                   Kociemba::new(4, Duration::from_secs(60)).solve(&cube));
+    }
+
+    #[test]
+    fn test_solve_errors() {
+        let mut cube = Cube::new();
+        cube.top_sides.swap(0, 1);
+        assert_eq!(Err(SolveError::Invalid(ValidationError::Parity)),
+                   Kociemba::default().solve(&cube));
+
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("R U F").unwrap());
+        assert_eq!(Err(SolveError::TooLong), Kociemba::new(2, Duration::from_secs(60)).solve(&cube));
+        assert_eq!(Err(SolveError::Timeout), Kociemba::with_node_budget(3, 10).solve(&cube));
+        assert_eq!(Ok(parse("F' U' R'").unwrap()),
+                   Kociemba::with_node_budget(3, 1_000_000).solve(&cube));
+    }
+}
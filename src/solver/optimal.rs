@@ -0,0 +1,408 @@
+//! An optimal solver: iterative deepening A* guided by pattern
+//! databases.  Each database holds the exact number of turns needed to
+//! solve some of the pieces, which never overestimates the turns
+//! needed for the whole cube.
+
+use std::time::{Duration, Instant};
+use cube::Cube;
+use cubies::{self, Cubies};
+use movement::{Movement, FACE_TURNS};
+use super::{cache, redundant, SolveError, Solver};
+
+/// Which pieces a pattern database follows.  Pieces are numbered as in
+/// `Cubies`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Pattern {
+    pub corners: Vec<u8>,
+    pub sides: Vec<u8>,
+}
+
+impl Pattern {
+    pub fn new(corners: &[u8], sides: &[u8]) -> Self {
+        Pattern { corners: corners.to_vec(), sides: sides.to_vec() }
+    }
+
+    /// The patterns of Korf's optimal solver: every corner, and two
+    /// halves of the sides.  Together the tables take about 170 MB
+    /// and over a minute to build.
+    pub fn korf() -> Vec<Pattern> {
+        vec![Pattern::new(&[0, 1, 2, 3, 4, 5, 6, 7], &[]),
+             Pattern::new(&[], &[0, 1, 2, 3, 4, 5]),
+             Pattern::new(&[], &[6, 7, 8, 9, 10, 11])]
+    }
+
+    fn corner_group(&self) -> Group {
+        Group { slots: 8, base: 3, pieces: self.corners.len() }
+    }
+
+    fn side_group(&self) -> Group {
+        Group { slots: 12, base: 2, pieces: self.sides.len() }
+    }
+
+    /// The number of ways the pieces can be arranged.
+    pub fn size(&self) -> usize {
+        self.corner_group().size() * self.side_group().size()
+    }
+
+    /// The name of the pattern's table, such as `pattern-c0.1-s4`.
+    pub fn name(&self) -> String {
+        let join = |pieces: &[u8]| {
+            pieces.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(".")
+        };
+        format!("pattern-c{}-s{}", join(&self.corners), join(&self.sides))
+    }
+}
+
+/// Some of the corners or some of the sides, each in one of `slots`
+/// slots and turned one of `base` ways.
+struct Group {
+    slots: usize,
+    base: usize,
+    pieces: usize,
+}
+
+impl Group {
+    /// How many orientations are stored.  When every piece is followed
+    /// the last orientation is decided by the others.
+    fn oriented(&self) -> usize {
+        if self.pieces == self.slots { self.pieces - 1 } else { self.pieces }
+    }
+
+    fn size(&self) -> usize {
+        let arrangements: usize = (self.slots - self.pieces + 1..self.slots + 1).product();
+        arrangements * self.base.pow(self.oriented() as u32)
+    }
+
+    fn encode(&self, slots: &[u8], orientations: &[u8]) -> usize {
+        let mut index = 0;
+        for i in 0..self.pieces {
+            let before = slots[..i].iter().filter(|&&s| s < slots[i]).count();
+            index = index * (self.slots - i) + slots[i] as usize - before;
+        }
+        for &o in &orientations[..self.oriented()] {
+            index = index * self.base + o as usize;
+        }
+        index
+    }
+
+    fn decode(&self, mut index: usize, slots: &mut [u8], orientations: &mut [u8]) {
+        let mut sum = 0;
+        for o in orientations[..self.oriented()].iter_mut().rev() {
+            *o = (index % self.base) as u8;
+            sum += *o as usize;
+            index /= self.base;
+        }
+        if self.oriented() < self.pieces {
+            orientations[self.pieces - 1] = ((self.base - sum % self.base) % self.base) as u8;
+        }
+        for i in (0..self.pieces).rev() {
+            slots[i] = (index % (self.slots - i)) as u8;
+            index /= self.slots - i;
+        }
+        // Each digit counts the free slots before the piece's slot.
+        let mut used = 0u16;
+        for slot in slots[..self.pieces].iter_mut() {
+            let mut free = *slot;
+            let mut s = 0;
+            while used & 1 << s != 0 || free > 0 {
+                if used & 1 << s == 0 {
+                    free -= 1;
+                }
+                s += 1;
+            }
+            used |= 1 << s;
+            *slot = s;
+        }
+    }
+}
+
+/// For each turn, where the piece in each slot goes and how much it
+/// turns on the way.
+fn piece_turns(corners: bool) -> Vec<Vec<(u8, u8)>> {
+    cubies::face_turns().iter().map(|turn| {
+        let (permutation, orientation) = if corners {
+            (&turn.corner_permutation[..], &turn.corner_orientation[..])
+        } else {
+            (&turn.side_permutation[..], &turn.side_orientation[..])
+        };
+        let mut moves = vec![(0, 0); permutation.len()];
+        for (to, &from) in permutation.iter().enumerate() {
+            moves[from as usize] = (to as u8, orientation[to]);
+        }
+        moves
+    }).collect()
+}
+
+/// The fewest turns needed to solve the pieces of a `Pattern`, for
+/// every way they can be arranged.
+pub struct PatternDatabase {
+    pattern: Pattern,
+    distances: Vec<u8>,
+}
+
+impl PatternDatabase {
+    /// Load the database from the installed cache, or else build it.
+    pub fn new(pattern: Pattern) -> Self {
+        let distances = cache::installed().get_or_build(&pattern.name(), pattern.size(), &|| {
+            PatternDatabase::build(&pattern)
+        });
+        PatternDatabase { pattern, distances }
+    }
+
+    /// Fill in the database by searching outwards from the solved
+    /// cube one depth at a time.
+    fn build(pattern: &Pattern) -> Vec<u8> {
+        const UNKNOWN: u8 = u8::MAX;
+        let (corners, sides) = (pattern.corner_group(), pattern.side_group());
+        let corner_turns = piece_turns(true);
+        let side_turns = piece_turns(false);
+        let side_size = sides.size();
+        let mut distances = vec![UNKNOWN; pattern.size()];
+        let solved = PatternDatabase::index_of(pattern, &Cubies::new());
+        distances[solved] = 0;
+        let mut filled = 1;
+        let mut depth = 0;
+        let (mut corner_slots, mut corner_orientations) = ([0; 8], [0; 8]);
+        let (mut side_slots, mut side_orientations) = ([0; 12], [0; 12]);
+        while filled < distances.len() {
+            let before = filled;
+            for index in 0..distances.len() {
+                if distances[index] != depth {
+                    continue;
+                }
+                corners.decode(index / side_size, &mut corner_slots, &mut corner_orientations);
+                sides.decode(index % side_size, &mut side_slots, &mut side_orientations);
+                for (corner_turn, side_turn) in corner_turns.iter().zip(side_turns.iter()) {
+                    let (mut cs, mut co) = (corner_slots, corner_orientations);
+                    for i in 0..corners.pieces {
+                        let (to, twist) = corner_turn[cs[i] as usize];
+                        cs[i] = to;
+                        co[i] = (co[i] + twist) % 3;
+                    }
+                    let (mut ss, mut so) = (side_slots, side_orientations);
+                    for i in 0..sides.pieces {
+                        let (to, flip) = side_turn[ss[i] as usize];
+                        ss[i] = to;
+                        so[i] ^= flip;
+                    }
+                    let next = corners.encode(&cs, &co) * side_size + sides.encode(&ss, &so);
+                    if distances[next] == UNKNOWN {
+                        distances[next] = depth + 1;
+                        filled += 1;
+                    }
+                }
+            }
+            if filled == before {
+                break;
+            }
+            depth += 1;
+        }
+        distances
+    }
+
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    fn index_of(pattern: &Pattern, cubies: &Cubies) -> usize {
+        let (mut corner_slots, mut corner_orientations) = ([0; 8], [0; 8]);
+        for (i, &piece) in pattern.corners.iter().enumerate() {
+            let slot = cubies.corner_permutation.iter().position(|&p| p == piece).unwrap();
+            corner_slots[i] = slot as u8;
+            corner_orientations[i] = cubies.corner_orientation[slot];
+        }
+        let (mut side_slots, mut side_orientations) = ([0; 12], [0; 12]);
+        for (i, &piece) in pattern.sides.iter().enumerate() {
+            let slot = cubies.side_permutation.iter().position(|&p| p == piece).unwrap();
+            side_slots[i] = slot as u8;
+            side_orientations[i] = cubies.side_orientation[slot];
+        }
+        let sides = pattern.side_group();
+        pattern.corner_group().encode(&corner_slots, &corner_orientations) * sides.size() +
+            sides.encode(&side_slots, &side_orientations)
+    }
+
+    /// The fewest turns that solve the pattern's pieces.
+    pub fn distance(&self, cubies: &Cubies) -> u8 {
+        self.distances[PatternDatabase::index_of(&self.pattern, cubies)]
+    }
+}
+
+/// An optimal solution and what it took to find it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OptimalSolution {
+    pub movements: Vec<Movement>,
+    /// The number of positions searched.
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
+impl OptimalSolution {
+    pub fn len(&self) -> usize {
+        self.movements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.movements.is_empty()
+    }
+}
+
+/// Finds the shortest solutions in face turns.
+pub struct Optimal {
+    databases: Vec<PatternDatabase>,
+}
+
+impl Optimal {
+    /// Build a database for each pattern.  See `Pattern::korf` for the
+    /// usual choice; smaller patterns build quickly but search slowly.
+    pub fn new(patterns: &[Pattern]) -> Self {
+        Optimal::with_databases(patterns.iter().cloned().map(PatternDatabase::new).collect())
+    }
+
+    pub fn with_databases(databases: Vec<PatternDatabase>) -> Self {
+        Optimal { databases }
+    }
+
+    pub fn databases(&self) -> &[PatternDatabase] {
+        &self.databases
+    }
+
+    fn estimate(&self, cubies: &Cubies) -> usize {
+        self.databases.iter().map(|d| d.distance(cubies) as usize).max().unwrap_or(0)
+    }
+
+    /// Find a shortest solution relative to the cube's centers,
+    /// counting the positions searched and the time taken.
+    pub fn search(&self, cube: &Cube) -> Result<OptimalSolution, SolveError> {
+        let start = Instant::now();
+        let cubies = Cubies::from_cube(cube)?;
+        cubies.validate()?;
+        let mut search = Search { optimal: self, solved: Cubies::new(), path: Vec::new(), nodes: 0 };
+        let mut bound = self.estimate(&cubies);
+        while !search.search(&cubies, bound) {
+            bound += 1;
+        }
+        Ok(OptimalSolution {
+            movements: search.path.iter().map(|&m| FACE_TURNS[m]).collect(),
+            nodes: search.nodes,
+            elapsed: start.elapsed(),
+        })
+    }
+}
+
+impl Solver for Optimal {
+    fn solve(&self, cube: &Cube) -> Result<Vec<Movement>, SolveError> {
+        self.search(cube).map(|solution| solution.movements)
+    }
+}
+
+struct Search<'a> {
+    optimal: &'a Optimal,
+    solved: Cubies,
+    /// The indices in `FACE_TURNS` of the turns made so far.
+    path: Vec<usize>,
+    nodes: u64,
+}
+
+impl<'a> Search<'a> {
+    /// Look for a solution of exactly `depth` more turns.
+    fn search(&mut self, cubies: &Cubies, depth: usize) -> bool {
+        self.nodes += 1;
+        if depth == 0 {
+            return *cubies == self.solved;
+        }
+        if self.optimal.estimate(cubies) > depth {
+            return false;
+        }
+        for (m, turn) in cubies::face_turns().iter().enumerate() {
+            if redundant(self.path.last().cloned(), m) {
+                continue;
+            }
+            self.path.push(m);
+            if self.search(&cubies.multiply(turn), depth - 1) {
+                return true;
+            }
+            self.path.pop();
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parser::parse;
+
+    fn small() -> Optimal {
+        Optimal::new(&[Pattern::new(&[0, 1, 2, 3], &[]),
+                       Pattern::new(&[4, 5, 6, 7], &[]),
+                       Pattern::new(&[], &[0, 1, 2, 3]),
+                       Pattern::new(&[], &[8, 9, 10, 11])])
+    }
+
+    #[test]
+    fn test_group() {
+        let group = Group { slots: 8, base: 3, pieces: 8 };
+        assert_eq!(88179840, group.size());
+        let group = Group { slots: 12, base: 2, pieces: 3 };
+        assert_eq!(10560, group.size());
+        let (mut slots, mut orientations) = ([0; 12], [0; 12]);
+        for index in 0..group.size() {
+            group.decode(index, &mut slots, &mut orientations);
+            assert_eq!(index, group.encode(&slots, &orientations));
+        }
+    }
+
+    #[test]
+    fn test_name() {
+        assert_eq!("pattern-c0.1-s", Pattern::new(&[0, 1], &[]).name());
+        assert_eq!("pattern-c-s4.10", Pattern::new(&[], &[4, 10]).name());
+    }
+
+    #[test]
+    fn test_pattern_database() {
+        let database = PatternDatabase::new(Pattern::new(&[0, 1], &[0]));
+        assert!(database.distances.iter().all(|&d| d != u8::MAX));
+        assert_eq!(0, database.distance(&Cubies::new()));
+        let mut cubies = Cubies::new();
+        assert!(cubies.apply(&FACE_TURNS[0]));
+        assert_eq!(1, database.distance(&cubies));
+        // The down face does not move the followed pieces.
+        let mut cubies = Cubies::new();
+        assert!(cubies.apply(&FACE_TURNS[9]));
+        assert_eq!(0, database.distance(&cubies));
+    }
+
+    #[test]
+    fn test_solve() {
+        let optimal = small();
+        let solution = optimal.search(&Cube::new()).unwrap();
+        assert!(solution.is_empty());
+
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("R U2 F' L D B'").unwrap());
+        let solution = optimal.search(&cube).unwrap();
+        assert_eq!(parse("B D' L' F U2 R'").unwrap(), solution.movements);
+        assert!(solution.nodes > 0);
+
+        // Slices and rotations from the REPL are solved relative to
+        // the centers.
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("M' U2 M U2 y").unwrap());
+        let solution = optimal.search(&cube).unwrap();
+        assert_eq!(6, solution.len());
+        cube.apply_all(&solution.movements);
+        assert!(cube.is_solved_in_any_orientation());
+    }
+
+    #[test]
+    fn test_solve_shorter() {
+        // `R L R'` is the same as `L`.
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("R L R' U2 R U2 R'").unwrap());
+        let solution = small().search(&cube).unwrap();
+        assert_eq!(5, solution.len());
+        cube.apply_all(&solution.movements);
+        assert!(cube.is_solved());
+    }
+}
@@ -0,0 +1,527 @@
+//! A solve in the style of the Roux method: a 1x2x3 block on the left,
+//! another on the right, the top corners (CMLL) and finally the last
+//! six edges (LSE) with only turns of the top and the middle slice.
+//!
+//! The middle slice carries its centers with it, so the pieces are
+//! tracked against the centers the cube started with rather than the
+//! ones it has now.
+
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+use coordinates::{permutation_index, permutation_from_index};
+use cube::{Cube, TOP};
+use cubies::Cubies;
+use movement::{Direction, Movement, FACE_TURNS};
+use parser::parse;
+use predicates::{OCLL_CASES, OLL_ALGORITHMS, PLL_ALGORITHMS};
+use simplify::simplify;
+use super::{cache, search, SolveError, Solver};
+
+/// The corner and side slots of a block.
+#[derive(Debug, Clone, Copy)]
+struct Block {
+    corners: [usize; 2],
+    sides: [usize; 3],
+}
+
+/// The bottom left corners and the bottom, front and back left sides.
+const FIRST_BLOCK: Block = Block { corners: [4, 7], sides: [11, 4, 5] };
+/// The bottom right corners and the bottom, front and back right sides.
+const SECOND_BLOCK: Block = Block { corners: [5, 6], sides: [9, 7, 6] };
+
+/// The turns allowed for the second block.
+const SECOND_BLOCK_TURNS: &str = "R R2 R' r r2 r' M M2 M' U U2 U'";
+/// The turns allowed for the last six edges.
+const LSE_TURNS: &str = "M M2 M' U U2 U'";
+
+/// The side slots of the last six edges: the top sides and the front
+/// and back bottom sides.
+const LSE_SIDES: [usize; 6] = [0, 1, 2, 3, 8, 10];
+
+const LSE_STATES: usize = 720 * 64 * 4 * 4;
+
+/// The pieces of `cube` relative to `centers` rather than its own.
+fn frame_cubies(cube: &Cube, centers: &Cube) -> Cubies {
+    let mut cube = cube.clone();
+    cube.centers = centers.centers;
+    Cubies::from_cube(&cube).expect("the cube must be valid")
+}
+
+/// What a movement does to the pieces of a solved cube, ignoring the
+/// centers.
+fn movement_cubies(movement: &Movement) -> Cubies {
+    let mut cube = Cube::new();
+    cube.apply(movement);
+    frame_cubies(&cube, &Cube::new())
+}
+
+/// Where each piece goes after each turn, as `slot * base +
+/// orientation`.
+fn piece_turns(turns: &[Cubies], corners: bool) -> Vec<Vec<u8>> {
+    let (slots, base) = if corners { (8, 3) } else { (12, 2) };
+    let mut table = vec![vec![0; turns.len()]; slots * base];
+    for (m, turn) in turns.iter().enumerate() {
+        for slot in 0..slots {
+            let (from, change) = if corners {
+                (turn.corner_permutation[slot] as usize, turn.corner_orientation[slot] as usize)
+            } else {
+                (turn.side_permutation[slot] as usize, turn.side_orientation[slot] as usize)
+            };
+            for o in 0..base {
+                table[from * base + o][m] = (slot * base + (o + change) % base) as u8;
+            }
+        }
+    }
+    table
+}
+
+/// How many turns each arrangement of some pieces is from `home`, with
+/// the state of piece `i` as digit `i` of the index in base `states`.
+fn distances(turns: &[Vec<u8>], home: &[u8]) -> Vec<u8> {
+    let states = turns.len();
+    let index = |pieces: &[u8]| pieces.iter().fold(0, |i, &p| i * states + p as usize);
+    let mut distances = vec![u8::MAX; states.pow(home.len() as u32)];
+    distances[index(home)] = 0;
+    let mut queue = VecDeque::new();
+    queue.push_back(home.to_vec());
+    while let Some(pieces) = queue.pop_front() {
+        let distance = distances[index(&pieces)];
+        let turned = |m| pieces.iter().map(|&p| turns[p as usize][m]).collect::<Vec<u8>>();
+        for next in (0..turns[0].len()).map(turned) {
+            if distances[index(&next)] == u8::MAX {
+                distances[index(&next)] = distance + 1;
+                queue.push_back(next);
+            }
+        }
+    }
+    distances
+}
+
+/// Builds a block with IDA*, estimating with how far its corners and
+/// its sides are from their places.
+struct BlockSearch {
+    turns: Vec<Movement>,
+    corner_turns: Vec<Vec<u8>>,
+    side_turns: Vec<Vec<u8>>,
+    corner_distances: Vec<u8>,
+    side_distances: Vec<u8>,
+}
+
+impl BlockSearch {
+    fn new(block: Block, turns: Vec<Movement>) -> Self {
+        let cubies: Vec<Cubies> = turns.iter().map(movement_cubies).collect();
+        let corner_turns = piece_turns(&cubies, true);
+        let side_turns = piece_turns(&cubies, false);
+        let corners: Vec<u8> = block.corners.iter().map(|&c| (c * 3) as u8).collect();
+        let sides: Vec<u8> = block.sides.iter().map(|&s| (s * 2) as u8).collect();
+        BlockSearch {
+            corner_distances: distances(&corner_turns, &corners),
+            side_distances: distances(&side_turns, &sides),
+            turns,
+            corner_turns,
+            side_turns,
+        }
+    }
+
+    fn estimate(&self, corners: [u8; 2], sides: [u8; 3]) -> usize {
+        let corner = self.corner_distances[corners[0] as usize * 24 + corners[1] as usize];
+        let side = self.side_distances[(sides[0] as usize * 24 + sides[1] as usize) * 24 +
+                                       sides[2] as usize];
+        corner.max(side) as usize
+    }
+
+    /// Turns of one direction are never made twice in a row and turns
+    /// that commute are only made in one order.
+    fn redundant(&self, last: Option<usize>, m: usize) -> bool {
+        last.is_some_and(|last| {
+            let (direction, last_direction) = (self.turns[m].direction, self.turns[last].direction);
+            direction.axis() == last_direction.axis() && m / 3 <= last / 3
+        })
+    }
+
+    fn search(&self, corners: [u8; 2], sides: [u8; 3], depth: usize,
+              path: &mut Vec<usize>) -> bool {
+        let estimate = self.estimate(corners, sides);
+        if estimate == 0 {
+            return true;
+        }
+        if estimate > depth {
+            return false;
+        }
+        for m in 0..self.turns.len() {
+            if self.redundant(path.last().cloned(), m) {
+                continue;
+            }
+            path.push(m);
+            if self.search(corners.map(|c| self.corner_turns[c as usize][m]),
+                           sides.map(|s| self.side_turns[s as usize][m]), depth - 1, path) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    /// The fewest turns that build the block.
+    fn solve(&self, cubies: &Cubies, block: Block) -> Vec<Movement> {
+        let corners = block.corners.map(|c| {
+            let slot = cubies.corner_permutation.iter().position(|&p| p as usize == c).unwrap();
+            (slot * 3) as u8 + cubies.corner_orientation[slot]
+        });
+        let sides = block.sides.map(|s| {
+            let slot = cubies.side_permutation.iter().position(|&p| p as usize == s).unwrap();
+            (slot * 2) as u8 + cubies.side_orientation[slot]
+        });
+        let mut path = Vec::new();
+        let mut depth = 0;
+        while !self.search(corners, sides, depth, &mut path) {
+            depth += 1;
+        }
+        path.iter().map(|&m| self.turns[m]).collect()
+    }
+}
+
+/// The last six edges as where each is and whether it is flipped, how
+/// many times the middle slice has turned and how many times the top
+/// has turned.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct LastSix {
+    permutation: [u8; 6],
+    orientation: [u8; 6],
+    middle: u8,
+    top: u8,
+}
+
+impl LastSix {
+    fn solved() -> Self {
+        LastSix { permutation: [0, 1, 2, 3, 4, 5], orientation: [0; 6], middle: 0, top: 0 }
+    }
+
+    /// Read the state of a cube whose blocks and corners are solved
+    /// relative to `reference`.
+    fn from_cube(cube: &Cube, reference: &Cube) -> Self {
+        let cubies = frame_cubies(cube, reference);
+        let mut last_six = LastSix::solved();
+        for (i, &slot) in LSE_SIDES.iter().enumerate() {
+            let piece = cubies.side_permutation[slot] as usize;
+            last_six.permutation[i] = LSE_SIDES.iter().position(|&s| s == piece).unwrap() as u8;
+            last_six.orientation[i] = cubies.side_orientation[slot];
+        }
+        let turned = |movement: &str, times: u8| {
+            let mut turned = reference.clone();
+            for _ in 0..times {
+                turned.apply_all(&parse(movement).unwrap());
+            }
+            turned
+        };
+        last_six.middle = (0..4).find(|&t| turned("M", t).centers == cube.centers)
+            .expect("only the middle slice may have moved the centers");
+        last_six.top = (0..4).find(|&t| turned("U", t).top_corners == cube.top_corners)
+            .expect("the top corners must be solved");
+        last_six
+    }
+
+    fn index(&self) -> usize {
+        let orientation = self.orientation.iter().fold(0, |i, &o| i * 2 + o as usize);
+        ((permutation_index(&self.permutation) * 64 + orientation) * 4 +
+         self.middle as usize) * 4 + self.top as usize
+    }
+
+    fn from_index(mut index: usize) -> Self {
+        let mut last_six = LastSix::solved();
+        last_six.top = (index % 4) as u8;
+        index /= 4;
+        last_six.middle = (index % 4) as u8;
+        index /= 4;
+        for o in last_six.orientation.iter_mut().rev() {
+            *o = (index % 2) as u8;
+            index /= 2;
+        }
+        permutation_from_index(index, &mut last_six.permutation);
+        last_six
+    }
+
+    /// Make a turn, given what it does to the pieces, how far it turns
+    /// the middle slice and how far it turns the top.
+    fn turn(&self, turn: &(Cubies, u8, u8)) -> Self {
+        let (ref cubies, middle, top) = *turn;
+        let mut next = *self;
+        for (i, &slot) in LSE_SIDES.iter().enumerate() {
+            let from = cubies.side_permutation[slot] as usize;
+            let j = LSE_SIDES.iter().position(|&s| s == from).unwrap();
+            next.permutation[i] = self.permutation[j];
+            next.orientation[i] = self.orientation[j] ^ cubies.side_orientation[slot];
+        }
+        next.middle = (self.middle + middle) % 4;
+        next.top = (self.top + top) % 4;
+        next
+    }
+}
+
+/// The turns of the last six edges, each with how far it turns the
+/// middle slice and the top.
+fn lse_turns() -> Vec<(Movement, (Cubies, u8, u8))> {
+    parse(LSE_TURNS).unwrap().into_iter().map(|movement| {
+        let quarter_turns = movement.times.quarter_turns();
+        let (middle, top) = if movement.direction == Direction::Middle {
+            (quarter_turns, 0)
+        } else {
+            (0, quarter_turns)
+        };
+        (movement, (movement_cubies(&movement), middle, top))
+    }).collect()
+}
+
+fn build_lse_table() -> Vec<u8> {
+    let turns = lse_turns();
+    let mut distances = vec![u8::MAX; LSE_STATES];
+    let solved = LastSix::solved().index();
+    distances[solved] = 0;
+    let mut queue = VecDeque::new();
+    queue.push_back(solved);
+    while let Some(index) = queue.pop_front() {
+        let state = LastSix::from_index(index);
+        for (_, turn) in &turns {
+            let next = state.turn(turn).index();
+            if distances[next] == u8::MAX {
+                distances[next] = distances[index] + 1;
+                queue.push_back(next);
+            }
+        }
+    }
+    distances
+}
+
+/// The searches and tables of the method.
+pub struct Tables {
+    first_block: BlockSearch,
+    second_block: BlockSearch,
+    lse_turns: Vec<(Movement, (Cubies, u8, u8))>,
+    /// How many turns of the top and middle slice solve each state of
+    /// the last six edges, by `LastSix::index`.
+    lse: Vec<u8>,
+}
+
+impl Tables {
+    pub fn new() -> Self {
+        Tables {
+            first_block: BlockSearch::new(FIRST_BLOCK, FACE_TURNS.to_vec()),
+            second_block: BlockSearch::new(SECOND_BLOCK, parse(SECOND_BLOCK_TURNS).unwrap()),
+            lse_turns: lse_turns(),
+            lse: cache::installed().get_or_build("roux-last-six-edges", LSE_STATES,
+                                                 &build_lse_table),
+        }
+    }
+
+    /// The tables shared by every solve, built when first needed.
+    pub fn get() -> &'static Tables {
+        static TABLES: OnceLock<Tables> = OnceLock::new();
+        TABLES.get_or_init(Tables::new)
+    }
+}
+
+impl Default for Tables {
+    fn default() -> Self {
+        Tables::new()
+    }
+}
+
+/// Are the pieces of `block` where they are in `reference`?
+fn block_solved(cube: &Cube, reference: &Cube, block: Block) -> bool {
+    let cubies = frame_cubies(cube, reference);
+    block.corners.iter().all(|&c| {
+        cubies.corner_permutation[c] as usize == c && cubies.corner_orientation[c] == 0
+    }) && block.sides.iter().all(|&s| {
+        cubies.side_permutation[s] as usize == s && cubies.side_orientation[s] == 0
+    })
+}
+
+/// Solve the top corners in two steps: orient them with the OLL
+/// algorithms that keep the top sides oriented, then swap them with
+/// the T and Y permutations.  The top may be left turned since the
+/// last six edges fix that.
+fn solve_cmll(cube: &mut Cube, reference: &Cube) -> Result<Vec<Movement>, SolveError> {
+    let top = |algorithms: &[&str]| {
+        let mut all = vec![parse("U").unwrap(), parse("U'").unwrap(), parse("U2").unwrap()];
+        all.extend(algorithms.iter().map(|a| parse(a).unwrap()));
+        all
+    };
+    let orient: Vec<&str> = OCLL_CASES.iter().map(|&number| OLL_ALGORITHMS[number - 1]).collect();
+    let permute: Vec<&str> = PLL_ALGORITHMS.iter()
+        .filter(|&&(name, _)| name == "T" || name == "Y")
+        .map(|&(_, algorithm)| algorithm)
+        .collect();
+    let color = reference.centers[TOP];
+    let mut movements = search(cube, &top(&orient), 2, &|c| {
+        c.top_corners.iter().all(|corner| corner.color(0) == color)
+    }).ok_or(SolveError::StageFailed("CMLL"))?;
+    cube.apply_all(&movements);
+    let solved: Vec<Cube> = (0..4).map(|t| {
+        let mut turned = reference.clone();
+        turned.apply_all(&parse("U").unwrap().repeat(t));
+        turned
+    }).collect();
+    let permutation = search(cube, &top(&permute), 2, &|c| {
+        solved.iter().any(|s| s.top_corners == c.top_corners)
+    }).ok_or(SolveError::StageFailed("CMLL"))?;
+    cube.apply_all(&permutation);
+    movements.extend(permutation);
+    Ok(movements)
+}
+
+/// The turns of each step of the solve.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct RouxSolution {
+    pub first_block: Vec<Movement>,
+    pub second_block: Vec<Movement>,
+    pub cmll: Vec<Movement>,
+    /// Only turns of the top and the middle slice.
+    pub lse: Vec<Movement>,
+}
+
+impl RouxSolution {
+    /// Every turn, in order.
+    pub fn movements(&self) -> Vec<Movement> {
+        [&self.first_block[..], &self.second_block, &self.cmll, &self.lse].concat()
+    }
+
+    /// The name and turns of each step.
+    pub fn stages(&self) -> Vec<(String, &[Movement])> {
+        vec![("first block".to_string(), &self.first_block[..]),
+             ("second block".to_string(), &self.second_block[..]),
+             ("CMLL".to_string(), &self.cmll[..]),
+             ("LSE".to_string(), &self.lse[..])]
+    }
+
+    pub fn len(&self) -> usize {
+        self.first_block.len() + self.second_block.len() + self.cmll.len() + self.lse.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Solves cubes with the Roux method.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Roux;
+
+impl Roux {
+    pub fn new() -> Self {
+        Roux
+    }
+
+    /// Solve the cube relative to its centers, step by step.  The
+    /// first block is built on the left with the fewest turns, the
+    /// second with turns of the right, the middle slice and the top.
+    pub fn solve_stages(&self, cube: &Cube) -> Result<RouxSolution, SolveError> {
+        cube.validate()?;
+        let tables = Tables::get();
+        let reference = Cube::solved_with_centers(cube.centers);
+        let mut cube = cube.clone();
+
+        let first_block = tables.first_block.solve(&frame_cubies(&cube, &reference), FIRST_BLOCK);
+        cube.apply_all(&first_block);
+        let second_block = tables.second_block.solve(&frame_cubies(&cube, &reference),
+                                                     SECOND_BLOCK);
+        cube.apply_all(&second_block);
+        if !block_solved(&cube, &reference, FIRST_BLOCK) ||
+            !block_solved(&cube, &reference, SECOND_BLOCK) {
+            return Err(SolveError::StageFailed("blocks"));
+        }
+
+        let cmll = solve_cmll(&mut cube, &reference)?;
+
+        let mut lse = Vec::new();
+        let mut state = LastSix::from_cube(&cube, &reference);
+        while tables.lse[state.index()] > 0 {
+            let distance = tables.lse[state.index()];
+            let (movement, turn) = tables.lse_turns.iter()
+                .find(|(_, turn)| tables.lse[state.turn(turn).index()] < distance)
+                .ok_or(SolveError::StageFailed("last six edges"))?;
+            state = state.turn(turn);
+            lse.push(*movement);
+        }
+        Ok(RouxSolution { first_block, second_block, cmll, lse })
+    }
+}
+
+impl Solver for Roux {
+    /// Solve the stages and join them, cancelling turns where they meet.
+    fn solve(&self, cube: &Cube) -> Result<Vec<Movement>, SolveError> {
+        self.solve_stages(cube).map(|solution| simplify(&solution.movements()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solver::benchmark::scrambles;
+    use validation::ValidationError;
+
+    #[test]
+    fn test_last_six() {
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("M U' M2 U2 M'").unwrap());
+        let state = LastSix::from_cube(&cube, &Cube::new());
+        assert_eq!((2, 1), (state.middle, state.top));
+        assert_eq!(state, LastSix::from_index(state.index()));
+
+        let turns = lse_turns();
+        let mut turned = LastSix::solved();
+        for movement in &parse("M U' M2 U2 M'").unwrap() {
+            let (_, turn) = turns.iter().find(|(m, _)| m == movement).unwrap();
+            turned = turned.turn(turn);
+        }
+        assert_eq!(state, turned);
+    }
+
+    #[test]
+    fn test_solve() {
+        for start in scrambles(3, 4) {
+            let solution = Roux::new().solve_stages(&start).unwrap();
+            let reference = Cube::new();
+            let mut cube = start.clone();
+            cube.apply_all(&solution.first_block);
+            assert!(block_solved(&cube, &reference, FIRST_BLOCK));
+            cube.apply_all(&solution.second_block);
+            assert!(block_solved(&cube, &reference, FIRST_BLOCK));
+            assert!(block_solved(&cube, &reference, SECOND_BLOCK));
+            assert!(solution.second_block.iter().all(|m| {
+                parse(SECOND_BLOCK_TURNS).unwrap().contains(m)
+            }));
+            cube.apply_all(&solution.cmll);
+            assert!((0..4).any(|t| {
+                let mut turned = cube.clone();
+                turned.apply_all(&parse("U").unwrap().repeat(t));
+                turned.top_corners == reference.top_corners
+            }));
+            cube.apply_all(&solution.lse);
+            assert!(solution.lse.iter().all(|m| parse(LSE_TURNS).unwrap().contains(m)));
+            assert!(cube.is_solved());
+            let movements = Roux::new().solve(&start).unwrap();
+            assert!(movements.len() <= solution.len());
+            assert_eq!(simplify(&movements), movements);
+            let mut cube = start.clone();
+            cube.apply_all(&movements);
+            assert!(cube.is_solved());
+        }
+    }
+
+    #[test]
+    fn test_solve_solved() {
+        assert!(Roux::new().solve_stages(&Cube::new()).unwrap().is_empty());
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("M' U2 M").unwrap());
+        let solution = Roux::new().solve_stages(&cube).unwrap();
+        assert_eq!(parse("M' U2 M").unwrap(), solution.lse);
+    }
+
+    #[test]
+    fn test_solve_invalid() {
+        let mut cube = Cube::new();
+        cube.top_corners[0].clockwise();
+        assert_eq!(Err(SolveError::Invalid(ValidationError::TwistedCorner)),
+                   Roux::new().solve(&cube));
+    }
+}
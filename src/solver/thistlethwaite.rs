@@ -0,0 +1,393 @@
+//! Thistlethwaite's algorithm.  The cube is brought through the
+//! groups
+//!
+//! * G1 = `<L, R, F, B, U2, D2>`
+//! * G2 = `<L, R, F2, B2, U2, D2>`
+//! * G3 = `<L2, R2, F2, B2, U2, D2>`
+//!
+//! and then solved, using only the turns of each group to reach the
+//! next one.  Every phase is solved in the fewest turns.
+
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+use coordinates::{orientation_index, orientation_from_index, permutation_index,
+                  permutation_from_index, combination_index, combination_from_index,
+                  flips, twists, half_turn_corners, in_thistlethwaite_group,
+                  CORNER_ORIENTATIONS, SIDE_ORIENTATIONS, CORNER_PERMUTATIONS,
+                  MIDDLE_SIDES, EQUATOR_SIDES, STANDING_SIDES};
+use cube::Cube;
+use cubies::{self, Cubies};
+use movement::{Movement, FACE_TURNS};
+use simplify::simplify;
+use super::{cache, SolveError, Solver};
+
+/// The indices in `FACE_TURNS` of the turns of each group, starting
+/// with every turn.
+const GROUP_TURNS: [&[usize]; 4] = [
+    &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17],
+    &[1, 3, 4, 5, 6, 7, 8, 10, 12, 13, 14, 15, 16, 17],
+    &[1, 3, 4, 5, 7, 10, 12, 13, 14, 16],
+    &[1, 4, 7, 10, 13, 16],
+];
+
+/// Which slots hold the given sides.
+fn placement(cubies: &Cubies, sides: &[u8; 4]) -> usize {
+    let mut chosen = [false; 12];
+    for (c, piece) in chosen.iter_mut().zip(cubies.side_permutation.iter()) {
+        *c = sides.contains(piece);
+    }
+    combination_index(&chosen)
+}
+
+/// A side permutation with the given sides in the slots chosen by a
+/// `placement`.
+fn placement_permutation(index: usize, sides: &[u8; 4]) -> [u8; 12] {
+    let mut chosen = [false; 12];
+    combination_from_index(index, 4, &mut chosen);
+    let mut others = (0..12).filter(|p| !sides.contains(p));
+    let mut sides = sides.iter().cloned();
+    let mut permutation = [0; 12];
+    for (p, &c) in permutation.iter_mut().zip(chosen.iter()) {
+        *p = if c { sides.next() } else { others.next() }.unwrap();
+    }
+    permutation
+}
+
+/// The order of the sides in the slots of a slice.
+fn slice_permutation(cubies: &Cubies, sides: &[u8; 4]) -> usize {
+    let mut order = [0; 4];
+    for (o, &slot) in order.iter_mut().zip(sides.iter()) {
+        let piece = cubies.side_permutation[slot as usize];
+        *o = sides.iter().position(|&s| s == piece).unwrap_or(0) as u8;
+    }
+    permutation_index(&order)
+}
+
+/// The sets of corner permutations that need the same turns to reach
+/// one in G3.
+struct Corners {
+    /// For each corner permutation, the set it is in.
+    class: Vec<u16>,
+    /// A corner permutation in each set.
+    representative: Vec<u16>,
+}
+
+impl Corners {
+    fn new() -> Self {
+        // Relabeling the pieces by a permutation of G3 does not change
+        // how far a permutation is from G3.
+        let mut class = vec![u16::MAX; CORNER_PERMUTATIONS];
+        let mut representative = Vec::new();
+        let mut permutation = [0; 8];
+        let mut g = [0; 8];
+        for index in 0..CORNER_PERMUTATIONS {
+            if class[index] != u16::MAX {
+                continue;
+            }
+            permutation_from_index(index, &mut permutation);
+            for &h in half_turn_corners() {
+                permutation_from_index(h as usize, &mut g);
+                let relabeled: Vec<u8> = permutation.iter().map(|&p| g[p as usize]).collect();
+                class[permutation_index(&relabeled)] = representative.len() as u16;
+            }
+            representative.push(index as u16);
+        }
+        Corners { class, representative }
+    }
+
+    fn get() -> &'static Corners {
+        static CORNERS: OnceLock<Corners> = OnceLock::new();
+        CORNERS.get_or_init(Corners::new)
+    }
+}
+
+/// Where each part of a phase's state goes after each turn of the
+/// phase, and how far each state is from the next group.
+struct Phase {
+    /// Each part's size and move table, indexed by state and then the
+    /// index of the turn in `GROUP_TURNS`.
+    parts: Vec<(usize, Vec<Vec<u16>>)>,
+    distances: Vec<u8>,
+}
+
+impl Phase {
+    /// Find the distances by searching outwards from the `sources`, or
+    /// load them from the installed cache.
+    fn new(group: usize, parts: Vec<(usize, Vec<Vec<u16>>)>, sources: &[usize]) -> Self {
+        let mut phase = Phase { parts, distances: Vec::new() };
+        let size = phase.parts.iter().map(|&(size, _)| size).product();
+        let name = format!("thistlethwaite-phase-{}", group + 1);
+        phase.distances = cache::installed().get_or_build(&name, size, &|| {
+            let mut distances = vec![u8::MAX; size];
+            let mut queue = VecDeque::new();
+            for &source in sources {
+                distances[source] = 0;
+                queue.push_back(source);
+            }
+            while let Some(index) = queue.pop_front() {
+                for m in 0..GROUP_TURNS[group].len() {
+                    let next = phase.turn(index, m);
+                    if distances[next] == u8::MAX {
+                        distances[next] = distances[index] + 1;
+                        queue.push_back(next);
+                    }
+                }
+            }
+            distances
+        });
+        phase
+    }
+
+    fn index(&self, parts: &[usize]) -> usize {
+        self.parts.iter().zip(parts.iter()).fold(0, |index, (&(size, _), &part)| {
+            index * size + part
+        })
+    }
+
+    fn turn(&self, mut index: usize, m: usize) -> usize {
+        let mut parts = [0; 4];
+        for (i, &(size, _)) in self.parts.iter().enumerate().rev() {
+            parts[i] = index % size;
+            index /= size;
+        }
+        for (part, (_, table)) in parts.iter_mut().zip(self.parts.iter()) {
+            *part = table[*part][m] as usize;
+        }
+        self.index(&parts[..self.parts.len()])
+    }
+}
+
+/// Build a move table by turning a cube made by `cubies` for each
+/// state.
+fn move_table(group: usize, size: usize, cubies: &dyn Fn(usize) -> Cubies,
+              coordinate: &dyn Fn(&Cubies) -> usize) -> (usize, Vec<Vec<u16>>) {
+    let face_turns = cubies::face_turns();
+    (size, (0..size).map(|index| {
+        let start = cubies(index);
+        GROUP_TURNS[group].iter()
+            .map(|&m| coordinate(&start.multiply(&face_turns[m])) as u16)
+            .collect()
+    }).collect())
+}
+
+/// The tables for the four phases.
+pub struct Tables {
+    phases: Vec<Phase>,
+}
+
+fn flip_coordinate(cubies: &Cubies) -> usize {
+    orientation_index(&flips(cubies), 2)
+}
+
+fn twist_coordinate(cubies: &Cubies) -> usize {
+    orientation_index(&twists(cubies), 3)
+}
+
+fn class_coordinate(cubies: &Cubies) -> usize {
+    Corners::get().class[permutation_index(&cubies.corner_permutation)] as usize
+}
+
+fn half_turn_coordinate(cubies: &Cubies) -> usize {
+    let index = permutation_index(&cubies.corner_permutation) as u16;
+    half_turn_corners().binary_search(&index).unwrap_or(0)
+}
+
+impl Tables {
+    pub fn new() -> Self {
+        let corners = Corners::get();
+        let solved = Cubies::new();
+
+        let flip = move_table(0, SIDE_ORIENTATIONS, &|index| {
+            let mut cubies = Cubies::new();
+            orientation_from_index(index, 2, &mut cubies.side_orientation);
+            cubies
+        }, &flip_coordinate);
+        let one = Phase::new(0, vec![flip], &[0]);
+
+        let twist = move_table(1, CORNER_ORIENTATIONS, &|index| {
+            let mut cubies = Cubies::new();
+            orientation_from_index(index, 3, &mut cubies.corner_orientation);
+            cubies
+        }, &twist_coordinate);
+        let middle = move_table(1, 495, &|index| {
+            let mut cubies = Cubies::new();
+            cubies.side_permutation = placement_permutation(index, &MIDDLE_SIDES);
+            cubies
+        }, &|cubies| placement(cubies, &MIDDLE_SIDES));
+        let two = Phase::new(1, vec![twist, middle], &[placement(&solved, &MIDDLE_SIDES)]);
+
+        let class = move_table(2, corners.representative.len(), &|index| {
+            let mut cubies = Cubies::new();
+            permutation_from_index(corners.representative[index] as usize,
+                                   &mut cubies.corner_permutation);
+            cubies
+        }, &class_coordinate);
+        let equator = move_table(2, 495, &|index| {
+            let mut cubies = Cubies::new();
+            cubies.side_permutation = placement_permutation(index, &EQUATOR_SIDES);
+            cubies
+        }, &|cubies| placement(cubies, &EQUATOR_SIDES));
+        let source = class_coordinate(&solved) * 495 + placement(&solved, &EQUATOR_SIDES);
+        let three = Phase::new(2, vec![class, equator], &[source]);
+
+        let half_turns = move_table(3, half_turn_corners().len(), &|index| {
+            let mut cubies = Cubies::new();
+            permutation_from_index(half_turn_corners()[index] as usize,
+                                   &mut cubies.corner_permutation);
+            cubies
+        }, &half_turn_coordinate);
+        let slices: Vec<_> = [MIDDLE_SIDES, EQUATOR_SIDES, STANDING_SIDES].iter().map(|sides| {
+            move_table(3, 24, &|index| {
+                let mut order = [0; 4];
+                permutation_from_index(index, &mut order);
+                let mut cubies = Cubies::new();
+                for (&slot, &o) in sides.iter().zip(order.iter()) {
+                    cubies.side_permutation[slot as usize] = sides[o as usize];
+                }
+                cubies
+            }, &|cubies| slice_permutation(cubies, sides))
+        }).collect();
+        let mut parts = vec![half_turns];
+        parts.extend(slices);
+        let four = Phase::new(3, parts, &[0]);
+
+        Tables { phases: vec![one, two, three, four] }
+    }
+
+    /// The tables shared by every search, built the first time they
+    /// are needed.
+    pub fn get() -> &'static Tables {
+        static TABLES: OnceLock<Tables> = OnceLock::new();
+        TABLES.get_or_init(Tables::new)
+    }
+}
+
+impl Default for Tables {
+    fn default() -> Self {
+        Tables::new()
+    }
+}
+
+/// The state of the cube as the phase's tables number it.
+fn phase_index(phase: &Phase, number: usize, cubies: &Cubies) -> usize {
+    match number {
+        0 => phase.index(&[flip_coordinate(cubies)]),
+        1 => phase.index(&[twist_coordinate(cubies), placement(cubies, &MIDDLE_SIDES)]),
+        2 => phase.index(&[class_coordinate(cubies), placement(cubies, &EQUATOR_SIDES)]),
+        _ => phase.index(&[half_turn_coordinate(cubies),
+                           slice_permutation(cubies, &MIDDLE_SIDES),
+                           slice_permutation(cubies, &EQUATOR_SIDES),
+                           slice_permutation(cubies, &STANDING_SIDES)]),
+    }
+}
+
+/// The turns of each of the four phases.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ThistlethwaiteSolution {
+    pub phases: [Vec<Movement>; 4],
+}
+
+impl ThistlethwaiteSolution {
+    /// Every turn, in order.
+    pub fn movements(&self) -> Vec<Movement> {
+        self.phases.concat()
+    }
+
+    pub fn len(&self) -> usize {
+        self.phases.iter().map(|p| p.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Solves cubes with Thistlethwaite's algorithm.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Thistlethwaite;
+
+impl Thistlethwaite {
+    pub fn new() -> Self {
+        Thistlethwaite
+    }
+
+    /// Solve the cube relative to its centers, phase by phase.
+    pub fn solve_phases(&self, cube: &Cube) -> Result<ThistlethwaiteSolution, SolveError> {
+        let mut cubies = Cubies::from_cube(cube)?;
+        cubies.validate()?;
+        let tables = Tables::get();
+        let face_turns = cubies::face_turns();
+        let mut solution = ThistlethwaiteSolution::default();
+        for (number, phase) in tables.phases.iter().enumerate() {
+            let mut index = phase_index(phase, number, &cubies);
+            while phase.distances[index] > 0 {
+                let m = (0..GROUP_TURNS[number].len())
+                    .find(|&m| phase.distances[phase.turn(index, m)] < phase.distances[index])
+                    .unwrap();
+                let turn = GROUP_TURNS[number][m];
+                index = phase.turn(index, m);
+                cubies = cubies.multiply(&face_turns[turn]);
+                solution.phases[number].push(FACE_TURNS[turn]);
+            }
+            debug_assert!(in_thistlethwaite_group(&cubies, number + 1));
+        }
+        Ok(solution)
+    }
+}
+
+impl Solver for Thistlethwaite {
+    /// Solve the phases and join them, cancelling turns where they meet.
+    fn solve(&self, cube: &Cube) -> Result<Vec<Movement>, SolveError> {
+        self.solve_phases(cube).map(|solution| simplify(&solution.movements()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parser::parse;
+    use validation::ValidationError;
+
+    #[test]
+    fn test_corners() {
+        let corners = Corners::get();
+        assert_eq!(420, corners.representative.len());
+    }
+
+    #[test]
+    fn test_solve() {
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("R U2 F' L D B' R2 D2 F U' L2 B R' D F2 U").unwrap());
+        let solution = Thistlethwaite::new().solve_phases(&cube).unwrap();
+        for (number, phase) in solution.phases.iter().enumerate() {
+            assert!(phase.iter().all(|m| {
+                GROUP_TURNS[number].iter().any(|&t| FACE_TURNS[t] == *m)
+            }));
+        }
+        assert!(solution.len() <= 52);
+        cube.apply_all(&solution.movements());
+        assert!(cube.is_solved());
+
+        assert!(Thistlethwaite::new().solve_phases(&Cube::new()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_solve_simplified() {
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("R U2 F' L D B' M E' S2 x r' u").unwrap());
+        let phases = Thistlethwaite::new().solve_phases(&cube).unwrap();
+        let solution = Thistlethwaite::new().solve(&cube).unwrap();
+        assert_eq!(simplify(&solution), solution);
+        assert!(solution.len() < phases.len());
+        cube.apply_all(&solution);
+        assert!(cube.is_solved_in_any_orientation());
+    }
+
+    #[test]
+    fn test_solve_invalid() {
+        let mut cube = Cube::new();
+        cube.top_sides[0].flip();
+        assert_eq!(Err(SolveError::Invalid(ValidationError::FlippedSide)),
+                   Thistlethwaite::new().solve(&cube));
+    }
+}
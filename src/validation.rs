@@ -0,0 +1,164 @@
+use std::error;
+use std::fmt;
+use corner::Corner;
+use cube::Cube;
+use cubies::{self, Cubies};
+use side::Side;
+
+/// Why a cube cannot be solved.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ValidationError {
+    /// The centers are not arranged as they are on a real cube.
+    InvalidCenters,
+    /// A corner's colors do not match any corner of a solved cube.
+    UnknownCorner(Corner),
+    /// A side's colors do not match any side of a solved cube.
+    UnknownSide(Side),
+    /// A corner appears twice, so another is missing.
+    DuplicateCorner { corner: Corner, missing: Corner },
+    /// A side appears twice, so another is missing.
+    DuplicateSide { side: Side, missing: Side },
+    /// The corners' twists do not add up to a whole turn.
+    TwistedCorner,
+    /// An odd number of sides are flipped.
+    FlippedSide,
+    /// Exactly two pieces are swapped: one of the corners or sides is
+    /// permuted by an odd number of swaps and the other is not.
+    Parity,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationError::InvalidCenters =>
+                write!(fmt, "the centers are not arranged as on a real cube"),
+            ValidationError::UnknownCorner(ref corner) =>
+                write!(fmt, "there is no corner {:?}", corner),
+            ValidationError::UnknownSide(ref side) =>
+                write!(fmt, "there is no side {:?}", side),
+            ValidationError::DuplicateCorner { ref corner, ref missing } =>
+                write!(fmt, "the corner {:?} appears twice and {:?} is missing",
+                       corner, missing),
+            ValidationError::DuplicateSide { ref side, ref missing } =>
+                write!(fmt, "the side {:?} appears twice and {:?} is missing", side, missing),
+            ValidationError::TwistedCorner => write!(fmt, "a corner is twisted"),
+            ValidationError::FlippedSide => write!(fmt, "a side is flipped"),
+            ValidationError::Parity => write!(fmt, "two pieces are swapped"),
+        }
+    }
+}
+
+impl error::Error for ValidationError {}
+
+impl Cube {
+    /// Check that the cube can be solved.
+    ///
+    /// The cube's fields are public, so it is possible to build a cube
+    /// that could never be reached by turning a real one.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        Cubies::from_cube(self)?.validate()
+    }
+}
+
+impl Cubies {
+    /// Check that the pieces can be solved.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.corner_orientation.iter().map(|&o| o as u32).sum::<u32>() % 3 != 0 {
+            return Err(ValidationError::TwistedCorner);
+        }
+        if self.side_orientation.iter().map(|&o| o as u32).sum::<u32>() % 2 != 0 {
+            return Err(ValidationError::FlippedSide);
+        }
+        if cubies::is_odd(&self.corner_permutation) != cubies::is_odd(&self.side_permutation) {
+            return Err(ValidationError::Parity);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use color::Color;
+    use parser::parse;
+
+    fn scrambled() -> Cube {
+        let mut cube = Cube::new();
+        cube.apply_all(&parse("R U2 F' L D B' M E' S2 x r' u").unwrap());
+        cube
+    }
+
+    #[test]
+    fn test_valid() {
+        assert_eq!(Ok(()), Cube::new().validate());
+        assert_eq!(Ok(()), scrambled().validate());
+        let mut cube = Cube::new();
+        cube.shuffle();
+        assert_eq!(Ok(()), cube.validate());
+    }
+
+    #[test]
+    fn test_twisted_corner() {
+        let mut cube = scrambled();
+        cube.top_corners[2].clockwise();
+        assert_eq!(Err(ValidationError::TwistedCorner), cube.validate());
+        cube.bottom_corners[1].clockwise();
+        assert_eq!(Err(ValidationError::TwistedCorner), cube.validate());
+        cube.bottom_corners[3].clockwise();
+        assert_eq!(Ok(()), cube.validate());
+    }
+
+    #[test]
+    fn test_flipped_side() {
+        let mut cube = scrambled();
+        cube.middle_sides[1].flip();
+        assert_eq!(Err(ValidationError::FlippedSide), cube.validate());
+        cube.top_sides[0].flip();
+        assert_eq!(Ok(()), cube.validate());
+    }
+
+    #[test]
+    fn test_parity() {
+        let mut cube = scrambled();
+        cube.top_sides.swap(0, 2);
+        assert_eq!(Err(ValidationError::Parity), cube.validate());
+        cube.bottom_corners.swap(1, 3);
+        assert_eq!(Ok(()), cube.validate());
+    }
+
+    #[test]
+    fn test_pieces() {
+        let mut cube = Cube::new();
+        cube.top_corners[0] = Corner::new(Color::Red, Color::Blue, Color::White);
+        assert_eq!(Err(ValidationError::UnknownCorner(
+            Corner::new(Color::Red, Color::Blue, Color::White))), cube.validate());
+
+        let mut cube = Cube::new();
+        cube.top_sides[0] = Side::new(Color::Red, Color::Orange);
+        assert_eq!(Err(ValidationError::UnknownSide(Side::new(Color::Red, Color::Orange))),
+                   cube.validate());
+
+        let mut cube = Cube::new();
+        cube.top_corners[1] = cube.top_corners[0].clone();
+        assert_eq!(Err(ValidationError::DuplicateCorner {
+                       corner: Corner::new(Color::Red, Color::White, Color::Blue),
+                       missing: Corner::new(Color::Red, Color::Blue, Color::Yellow),
+                   }),
+                   cube.validate());
+
+        let mut cube = Cube::new();
+        cube.bottom_sides[2] = Side::new(Color::White, Color::Green);
+        assert_eq!(Err(ValidationError::DuplicateSide {
+                       side: Side::new(Color::White, Color::Green),
+                       missing: Side::new(Color::Orange, Color::Yellow),
+                   }),
+                   cube.validate());
+    }
+
+    #[test]
+    fn test_invalid_centers() {
+        let mut cube = Cube::new();
+        cube.centers.swap(0, 1);
+        assert_eq!(Err(ValidationError::InvalidCenters), cube.validate());
+    }
+}